@@ -0,0 +1,35 @@
+#![no_main]
+
+// Arbitrary bytes as a ROM plus the keys held each frame. The first byte picks the platform and
+// how many frames to run, then two bytes of keypad mask per frame, the rest is the ROM.
+
+use chip_8_emulator::emulation::{quirks::Platform, Emulation};
+use libfuzzer_sys::fuzz_target;
+
+const PLATFORMS: [Platform; 4] = [Platform::Chip8, Platform::ModernChip8, Platform::SuperChip, Platform::XoChip];
+const MAX_FRAMES: usize = 16;
+const INSTRUCTIONS_PER_FRAME: u32 = 1000;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&header, data)) = data.split_first() else {
+        return;
+    };
+    let platform = PLATFORMS[(header & 0b11) as usize];
+    let frames = (header >> 2) as usize % MAX_FRAMES + 1;
+    if data.len() < frames * 2 {
+        return;
+    }
+    let (keys, rom) = data.split_at(frames * 2);
+
+    let mut emulation = Emulation::new(platform, 0);
+    if emulation.load_rom(rom).is_err() {
+        return;
+    }
+    for mask in keys.chunks(2) {
+        let mask = u16::from_le_bytes([mask[0], mask[1]]);
+        for (key, pressed) in emulation.keypad.iter_mut().enumerate() {
+            *pressed = mask & (1 << key) != 0;
+        }
+        emulation.run_frame(INSTRUCTIONS_PER_FRAME);
+    }
+});
@@ -0,0 +1,22 @@
+#![no_main]
+
+// Arbitrary bytes as a save state. Whatever loads has to run and save again without panicking,
+// and a state that failed to load must leave the machine as it was.
+
+use chip_8_emulator::emulation::{quirks::Platform, Emulation};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut emulation = Emulation::new(Platform::XoChip, 0);
+    let before = emulation.save_state();
+
+    if emulation.load_state(data).is_err() {
+        assert_eq!(emulation.save_state(), before);
+        return;
+    }
+    for _ in 0..4 {
+        emulation.run_frame(1000);
+    }
+    let saved = emulation.save_state();
+    emulation.load_state(&saved).expect("a saved state loads back");
+});
@@ -0,0 +1,208 @@
+use std::{fs, path::{Path, PathBuf}};
+
+use crate::{database, palette::Rgb, text};
+
+// The launcher drawn in place of the game: ROM files in a directory, recently played ones first
+
+pub const WIDTH: usize = 320;
+pub const HEIGHT: usize = 160;
+
+const EXTENSIONS: [&str; 3] = ["ch8", "c8", "rom"];
+const MAX_RECENT: usize = 8;
+// Text rows between the header and footer lines
+const VISIBLE_ROWS: usize = HEIGHT / text::CELL_HEIGHT - 2;
+const COLUMNS: usize = WIDTH / text::CELL_WIDTH;
+
+const BACKGROUND: Rgb = (0x10, 0x10, 0x18);
+const FOREGROUND: Rgb = (0xE0, 0xE0, 0xE0);
+const DIM: Rgb = (0x80, 0x80, 0x90);
+const HIGHLIGHT: Rgb = (0x30, 0x60, 0xC0);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MenuKey {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Select,
+    Back,
+}
+
+struct Entry {
+    path: PathBuf,
+    // Database title, or the file name for unknown ROMs
+    title: String,
+    recent: bool,
+}
+
+pub struct RomBrowser {
+    dir: PathBuf,
+    database_dir: String,
+    recent_path: PathBuf,
+    entries: Vec<Entry>,
+    selected: usize,
+    // First entry shown at the top of the list
+    scroll: usize,
+    open: bool,
+    frame: Vec<Rgb>,
+}
+
+impl RomBrowser {
+
+    pub fn new(dir: PathBuf, database_dir: &str, recent_path: PathBuf) -> Self {
+        Self {
+            dir,
+            database_dir: database_dir.to_string(),
+            recent_path,
+            entries: Vec::new(),
+            selected: 0,
+            scroll: 0,
+            open: false,
+            frame: vec![BACKGROUND; WIDTH * HEIGHT],
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    // Rescans the directory each time so new files and recent games show up
+    pub fn open(&mut self) {
+        let recent = read_recent(&self.recent_path);
+        let mut paths: Vec<(PathBuf, bool)> = recent.into_iter()
+            .filter(|path| path.is_file())
+            .map(|path| (path, true))
+            .collect();
+
+        let mut files: Vec<PathBuf> = fs::read_dir(&self.dir)
+            .map(|entries| entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect())
+            .unwrap_or_default();
+        files.retain(|path| is_rom(path) && !paths.iter().any(|(recent, _)| same_file(recent, path)));
+        files.sort();
+        paths.extend(files.into_iter().map(|path| (path, false)));
+
+        let hashes: Vec<String> = paths.iter()
+            .map(|(path, _)| fs::read(path).map(|rom| database::rom_hash(&rom)).unwrap_or_default())
+            .collect();
+        let titles = database::titles(&self.database_dir, &hashes).unwrap_or_else(|err| {
+            eprintln!("Could not read the ROM database: {}", err);
+            vec![None; hashes.len()]
+        });
+
+        self.entries = paths.into_iter()
+            .zip(titles)
+            .map(|((path, recent), title)| {
+                let title = title.unwrap_or_else(|| path.file_name().unwrap_or_default().to_string_lossy().into_owned());
+                Entry { path, title, recent }
+            })
+            .collect();
+        self.selected = 0;
+        self.scroll = 0;
+        self.open = true;
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+
+    // Returns the ROM to load once one is picked, which also closes the browser
+    pub fn handle_key(&mut self, key: MenuKey) -> Option<PathBuf> {
+        let last = self.entries.len().saturating_sub(1);
+        match key {
+            MenuKey::Up => self.selected = self.selected.saturating_sub(1),
+            MenuKey::Down => self.selected = (self.selected + 1).min(last),
+            MenuKey::PageUp => self.selected = self.selected.saturating_sub(VISIBLE_ROWS),
+            MenuKey::PageDown => self.selected = (self.selected + VISIBLE_ROWS).min(last),
+            MenuKey::Home => self.selected = 0,
+            MenuKey::End => self.selected = last,
+            MenuKey::Select => {
+                let path = self.entries.get(self.selected)?.path.clone();
+                self.close();
+                return Some(path);
+            },
+            MenuKey::Back => self.close(),
+        }
+
+        // Keep the selection on screen
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + VISIBLE_ROWS {
+            self.scroll = self.selected + 1 - VISIBLE_ROWS;
+        }
+        None
+    }
+
+    pub fn draw(&mut self) -> &[Rgb] {
+        let frame = &mut self.frame;
+        frame.fill(BACKGROUND);
+
+        text::fill_rect(frame, WIDTH, 0, 0, WIDTH, text::CELL_HEIGHT, DIM);
+        let header = format!("ROMS IN {}", self.dir.display());
+        text::draw(frame, WIDTH, 1, 1, &truncate(&header, COLUMNS), BACKGROUND);
+
+        if self.entries.is_empty() {
+            text::draw(frame, WIDTH, 1, text::CELL_HEIGHT + 1, "NO ROMS FOUND", DIM);
+        }
+        for (row, entry) in self.entries.iter().enumerate().skip(self.scroll).take(VISIBLE_ROWS) {
+            let y = (row - self.scroll + 1) * text::CELL_HEIGHT;
+            let color = if row == self.selected {
+                text::fill_rect(frame, WIDTH, 0, y, WIDTH, text::CELL_HEIGHT, HIGHLIGHT);
+                FOREGROUND
+            } else if entry.recent {
+                FOREGROUND
+            } else {
+                DIM
+            };
+            let marker = if entry.recent { '*' } else { ' ' };
+            let line = format!("{}{}", marker, entry.title);
+            text::draw(frame, WIDTH, 1, y + 1, &truncate(&line, COLUMNS), color);
+        }
+
+        let footer_y = HEIGHT - text::CELL_HEIGHT;
+        text::draw(frame, WIDTH, 1, footer_y + 1, "ENTER PLAY   ESC BACK   * RECENT", DIM);
+
+        &self.frame
+    }
+
+}
+
+// Moves `rom` to the front of the recent games file
+pub fn remember(recent_path: &Path, rom: &Path) -> Result<(), String> {
+    let mut recent = read_recent(recent_path);
+    recent.retain(|path| !same_file(path, rom));
+    recent.insert(0, rom.to_path_buf());
+    recent.truncate(MAX_RECENT);
+
+    let text: String = recent.iter().map(|path| format!("{}\n", path.display())).collect();
+    fs::write(recent_path, text).map_err(|err| format!("{}: {}", recent_path.display(), err))
+}
+
+fn read_recent(recent_path: &Path) -> Vec<PathBuf> {
+    fs::read_to_string(recent_path)
+        .map(|text| text.lines().filter(|line| !line.is_empty()).map(PathBuf::from).collect())
+        .unwrap_or_default()
+}
+
+fn is_rom(path: &Path) -> bool {
+    path.is_file() && path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| EXTENSIONS.iter().any(|known| known.eq_ignore_ascii_case(extension)))
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn truncate(text: &str, columns: usize) -> String {
+    if text.chars().count() <= columns {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(columns - 3).collect();
+    truncated.push_str("...");
+    truncated
+}
@@ -0,0 +1,194 @@
+use std::path::Path;
+
+use crate::{emulation::quirks::Platform, filter::FilterMode, netplay::Role, palette::Palette, tui::RenderMode};
+
+pub const USAGE: &str = "\
+usage: chip-8_emulator [options] [rom]
+
+Without a ROM the SDL window opens on the ROM browser (F1).
+
+options:
+    -i, --ipf <n>            instructions executed per 60Hz frame (default 10)
+    -s, --scale <n>          initial window (or sixel/kitty image) pixels per CHIP-8 pixel (default 10)
+        --stretch            fill the window keeping the aspect ratio, not just whole pixel sizes
+    -p, --platform <name>    quirk profile: chip8, modern, schip or xochip (default chip8)
+        --palette <palette>  classic, green, amber, lcd, octo or 2/4 RRGGBB colours like 000000,ffffff
+        --filter <mode>      flicker filter: off, blend, phosphor or vblank (default off)
+        --frontend <name>    sdl, terminal (half blocks), braille, sixel or kitty (default sdl when built with it)
+        --headless <frames>  run without a window and print the final frame
+        --rom-dir <dir>      directory listed by the ROM browser (default: the ROM's, or roms)
+        --seed <n>           seed for the CXNN random number generator
+        --paused             start paused
+        --fast-forward <n>   speed multiplier while the fast-forward key is held (default 4)
+        --debug              start paused with the debugger window open
+        --trace              print every instruction executed to stderr
+        --frame-times        print frame time statistics to stderr on exit
+        --host <port>        wait for a second player to join a netplay game on this port
+        --join <host:port>   join a netplay game, the ROM must be the same as the host's
+        --input-delay <n>    frames between a key press and its effect in netplay, set by the host (default 2)
+        --gdb <port>         wait for GDB to connect on this local port and let it control the program
+        --control <port>     accept newline-delimited JSON commands (load_rom, step, press, registers, ...) on this local port
+    -h, --help               print this message";
+
+pub enum FrontendKind {
+    #[cfg(feature = "sdl")]
+    Sdl,
+    Terminal(RenderMode),
+}
+
+pub struct Options {
+    // Only the SDL frontend can start without one
+    pub rom: Option<String>,
+    // Left unset when not given so ROM settings can fill them in
+    pub instructions_per_frame: Option<u32>,
+    pub scale: u32,
+    pub integer_scale: bool,
+    pub platform: Option<Platform>,
+    pub palette: Option<Palette>,
+    pub filter: FilterMode,
+    pub frontend: FrontendKind,
+    pub headless: Option<u32>,
+    pub rom_dir: Option<String>,
+    pub seed: Option<u64>,
+    pub paused: bool,
+    pub fast_forward: u32,
+    pub debug: bool,
+    pub trace: bool,
+    pub frame_times: bool,
+    pub netplay: Option<Role>,
+    pub input_delay: u32,
+    pub gdb: Option<u16>,
+    pub control: Option<u16>,
+}
+
+// Ok(None) means help was requested
+pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut options = Options {
+        rom: None,
+        instructions_per_frame: None,
+        scale: 10,
+        integer_scale: true,
+        platform: None,
+        palette: None,
+        filter: FilterMode::Off,
+        #[cfg(feature = "sdl")]
+        frontend: FrontendKind::Sdl,
+        #[cfg(not(feature = "sdl"))]
+        frontend: FrontendKind::Terminal(RenderMode::HalfBlock),
+        headless: None,
+        rom_dir: None,
+        seed: None,
+        paused: false,
+        fast_forward: 4,
+        debug: false,
+        trace: false,
+        frame_times: false,
+        netplay: None,
+        input_delay: 2,
+        gdb: None,
+        control: None,
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-i" | "--ipf" => {
+                let instructions_per_frame = parse_number(&arg, args.next())?;
+                if instructions_per_frame == 0 {
+                    return Err(format!("{} must be at least 1", arg));
+                }
+                options.instructions_per_frame = Some(instructions_per_frame);
+            },
+            "-s" | "--scale" => {
+                options.scale = parse_number(&arg, args.next())?;
+                if options.scale == 0 {
+                    return Err(format!("{} must be at least 1", arg));
+                }
+            },
+            "-p" | "--platform" => {
+                let name = value(&arg, args.next())?;
+                options.platform = Some(Platform::from_name(&name).ok_or_else(|| {
+                    format!("unknown platform `{}`, expected chip8, modern, schip or xochip", name)
+                })?);
+            },
+            "--palette" => {
+                options.palette = Some(Palette::parse(&value(&arg, args.next())?)?);
+            },
+            "--filter" => {
+                let name = value(&arg, args.next())?;
+                options.filter = FilterMode::from_name(&name).ok_or_else(|| {
+                    format!("unknown filter `{}`, expected off, blend, phosphor or vblank", name)
+                })?;
+            },
+            "--frontend" => {
+                options.frontend = match value(&arg, args.next())?.as_str() {
+                    #[cfg(feature = "sdl")]
+                    "sdl" => FrontendKind::Sdl,
+                    #[cfg(not(feature = "sdl"))]
+                    "sdl" => return Err("this build does not include the SDL frontend".to_string()),
+                    "terminal" => FrontendKind::Terminal(RenderMode::HalfBlock),
+                    "braille" => FrontendKind::Terminal(RenderMode::Braille),
+                    "sixel" => FrontendKind::Terminal(RenderMode::Sixel),
+                    "kitty" => FrontendKind::Terminal(RenderMode::Kitty),
+                    name => return Err(format!("unknown frontend `{}`, expected sdl, terminal, braille, sixel or kitty", name)),
+                };
+            },
+            "--stretch" => options.integer_scale = false,
+            "--headless" => options.headless = Some(parse_number(&arg, args.next())?),
+            "--rom-dir" => options.rom_dir = Some(value(&arg, args.next())?),
+            "--seed" => options.seed = Some(parse_number(&arg, args.next())?),
+            "--paused" => options.paused = true,
+            "--fast-forward" => {
+                options.fast_forward = parse_number(&arg, args.next())?;
+                if options.fast_forward == 0 {
+                    return Err(format!("{} must be at least 1", arg));
+                }
+            },
+            "--debug" => options.debug = true,
+            "--trace" => options.trace = true,
+            "--frame-times" => options.frame_times = true,
+            "--host" => options.netplay = Some(Role::Host(parse_number(&arg, args.next())?)),
+            "--join" => options.netplay = Some(Role::Join(value(&arg, args.next())?)),
+            "--gdb" => options.gdb = Some(parse_number(&arg, args.next())?),
+            "--control" => options.control = Some(parse_number(&arg, args.next())?),
+            "--input-delay" => {
+                options.input_delay = parse_number(&arg, args.next())?;
+                if options.input_delay > 60 {
+                    return Err(format!("{} is at most 60 frames", arg));
+                }
+            },
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{}`", arg)),
+            _ => {
+                if options.rom.is_some() {
+                    return Err(format!("unexpected argument `{}`, only one ROM can be loaded", arg));
+                }
+                options.rom = Some(arg);
+            }
+        }
+    }
+
+    match &options.rom {
+        Some(rom) if !Path::new(rom).is_file() => return Err(format!("ROM `{}` does not exist", rom)),
+        None if options.headless.is_some()
+            || options.netplay.is_some()
+            || options.gdb.is_some()
+            || matches!(options.frontend, FrontendKind::Terminal(_)) => {
+            return Err("no ROM given".to_string());
+        },
+        _ => {}
+    }
+    if options.debug && (options.headless.is_some() || matches!(options.frontend, FrontendKind::Terminal(_))) {
+        return Err("--debug needs a window, --trace works everywhere".to_string());
+    }
+
+    Ok(Some(options))
+}
+
+fn value(option: &str, value: Option<String>) -> Result<String, String> {
+    value.ok_or_else(|| format!("{} expects a value", option))
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, text: Option<String>) -> Result<T, String> {
+    let text = value(option, text)?;
+    text.parse().map_err(|_| format!("{} expects a positive number, got `{}`", option, text))
+}
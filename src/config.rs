@@ -0,0 +1,112 @@
+use std::{fs, io::ErrorKind};
+
+// A minimal ini style config file:
+//
+//   [section]
+//   key = value   # comment
+//
+// A `#` only starts a comment at the start of a line or after whitespace that follows
+// some of the value, so values such as `#RRGGBB` colours are kept whole.
+// Entries keep their file order so later lines can override earlier ones.
+pub struct Config {
+    sections: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl Config {
+
+    pub fn empty() -> Self {
+        Self {
+            sections: Vec::new(),
+        }
+    }
+
+    // A missing file is not an error, it just means nothing is overridden
+    pub fn load(path: &str) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text).map_err(|err| format!("{}: {}", path, err)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Self::empty()),
+            Err(err) => Err(format!("{}: {}", path, err)),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut sections: Vec<(String, Vec<(String, String)>)> = Vec::new();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = strip_comment(line).trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                let name = line[1..line.len()-1].trim().to_lowercase();
+                sections.push((name, Vec::new()));
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!("line {}: expected `key = value`", number + 1));
+            };
+            let Some((_, entries)) = sections.last_mut() else {
+                return Err(format!("line {}: entry outside of a [section]", number + 1));
+            };
+            entries.push((key.trim().to_string(), value.trim().to_string()));
+        }
+
+        Ok(Self {
+            sections,
+        })
+    }
+
+    pub fn section(&self, name: &str) -> Vec<(&str, &str)> {
+        self.sections.iter()
+            .filter(|(section, _)| section == name)
+            .flat_map(|(_, entries)| entries.iter())
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect()
+    }
+
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.section(section)
+            .into_iter()
+            .rev()
+            .find(|(entry, _)| entry.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value)
+    }
+
+}
+
+fn strip_comment(line: &str) -> &str {
+    if line.trim_start().starts_with('#') {
+        return "";
+    }
+    // The first character of a value is never a comment, it may be the `#` of a colour
+    let after_value_start = match line.find('=') {
+        Some(equals) => line.len() - line[equals + 1..].trim_start().len() + 1,
+        None => 0,
+    };
+    line.char_indices()
+        .skip_while(|(index, _)| *index < after_value_start)
+        .find(|(index, c)| *c == '#' && line[..*index].ends_with(char::is_whitespace))
+        .map_or(line, |(index, _)| &line[..index])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comments() {
+        let config = Config::parse("# about\n[palette]  # colours\nname = dark # not light\nkey=a#b\n").unwrap();
+        assert_eq!(config.get("palette", "name"), Some("dark"));
+        assert_eq!(config.get("palette", "key"), Some("a#b"));
+    }
+
+    #[test]
+    fn hex_colours() {
+        let config = Config::parse("[palette]\ncolors = #000000,#FFFFFF  # black on white\nbackground = #112233\n").unwrap();
+        assert_eq!(config.get("palette", "colors"), Some("#000000,#FFFFFF"));
+        assert_eq!(config.get("palette", "background"), Some("#112233"));
+    }
+}
@@ -0,0 +1,269 @@
+use std::{
+    io::{ErrorKind, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::PathBuf,
+    time::Duration,
+};
+
+use serde_json::{json, Map, Value};
+
+use crate::emulation::Emulation;
+
+// A control socket for scripts and external tools. Each line sent to it is a JSON object
+// naming a command, and each gets one line back, `{"ok": true, ...}` or `{"ok": false, "error": ...}`:
+//
+//   {"cmd": "load_rom", "path": "roms/pong.rom"}   {"cmd": "reset"}
+//   {"cmd": "pause"}   {"cmd": "resume"}   {"cmd": "step", "frames": 10}   {"cmd": "quit"}
+//   {"cmd": "press", "key": 5}   {"cmd": "release", "key": 5}
+//   {"cmd": "registers"}   {"cmd": "memory", "address": 512, "length": 16}   {"cmd": "framebuffer"}
+//   {"cmd": "save_state"}   {"cmd": "load_state", "state": "<hex from save_state>"}
+//
+// Commands that only touch the machine are handled here, the rest go to the main loop.
+// One client is served at a time, a new connection replaces the old one.
+
+// Replies a client hasn't read yet are kept up to this size, past it the client is dropped
+const MAX_UNSENT: usize = 16 << 20;
+// How long replies still unsent when the emulator exits may take
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+// What the main loop has to do, it answers through `reply`
+pub enum Request {
+    LoadRom(PathBuf),
+    Pause(bool),
+    Step(u32),
+    Quit,
+}
+
+pub struct ControlServer {
+    listener: TcpListener,
+    client: Option<TcpStream>,
+    buffer: Vec<u8>,
+    // Replies the socket wouldn't take yet, sent before anything else on the next poll
+    unsent: Vec<u8>,
+    // Keys held down through the socket, on top of the frontend's
+    pub keys: [bool; 16],
+}
+
+impl ControlServer {
+
+    pub fn listen(port: u16) -> Result<Self, String> {
+        let listener = TcpListener::bind(("127.0.0.1", port)).map_err(|err| format!("port {}: {}", port, err))?;
+        listener.set_nonblocking(true).map_err(|err| err.to_string())?;
+
+        Ok(Self {
+            listener,
+            client: None,
+            buffer: Vec::new(),
+            unsent: Vec::new(),
+            keys: [false; 16],
+        })
+    }
+
+    // Answers commands in the order they came until one needs the main loop, which then has to
+    // `reply` before calling this again. Returns None once everything received so far is done.
+    pub fn next_request(&mut self, emulation: &mut Emulation) -> Option<Request> {
+        self.send();
+        self.receive();
+
+        while let Some(end) = self.buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let command = match serde_json::from_str::<Value>(&line) {
+                Ok(Value::Object(command)) => command,
+                Ok(_) => {
+                    self.reply(Err("expected a JSON object".to_string()));
+                    continue;
+                },
+                Err(err) => {
+                    self.reply(Err(format!("invalid JSON: {}", err)));
+                    continue;
+                },
+            };
+            match self.execute(&command, emulation) {
+                Ok(Some(request)) => return Some(request),
+                Ok(None) => {},
+                Err(err) => self.reply(Err(err)),
+            }
+        }
+        None
+    }
+
+    // `fields` are added to the reply next to `ok`
+    pub fn reply(&mut self, result: Result<Map<String, Value>, String>) {
+        let reply = match result {
+            Ok(mut fields) => {
+                fields.insert("ok".to_string(), Value::Bool(true));
+                Value::Object(fields)
+            },
+            Err(err) => json!({ "ok": false, "error": err }),
+        };
+        if self.client.is_none() {
+            return;
+        }
+        self.unsent.extend_from_slice(format!("{}\n", reply).as_bytes());
+        self.send();
+    }
+
+    // Writes as much as the nonblocking socket takes, the rest waits for the next poll
+    fn send(&mut self) {
+        while let Some(client) = self.client.as_mut() {
+            if self.unsent.is_empty() {
+                return;
+            }
+            match client.write(&self.unsent) {
+                Ok(0) => self.drop_client(),
+                Ok(written) => { self.unsent.drain(..written); },
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {},
+                Err(_) => self.drop_client(),
+            }
+        }
+        if self.unsent.len() > MAX_UNSENT {
+            self.drop_client();
+        }
+    }
+
+    fn receive(&mut self) {
+        if let Ok((stream, _)) = self.listener.accept() {
+            if stream.set_nonblocking(true).is_ok() {
+                self.drop_client();
+                self.client = Some(stream);
+            }
+        }
+
+        let mut chunk = [0; 4096];
+        while let Some(client) = self.client.as_mut() {
+            match client.read(&mut chunk) {
+                Ok(0) => self.drop_client(),
+                Ok(read) => self.buffer.extend_from_slice(&chunk[..read]),
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {},
+                Err(_) => self.drop_client(),
+            }
+        }
+    }
+
+    // Keys held by a client that went away are let go
+    fn drop_client(&mut self) {
+        self.client = None;
+        self.buffer.clear();
+        self.unsent.clear();
+        self.keys = [false; 16];
+    }
+
+    fn execute(&mut self, command: &Map<String, Value>, emulation: &mut Emulation) -> Result<Option<Request>, String> {
+        let name = command.get("cmd").and_then(Value::as_str).ok_or("missing \"cmd\"")?;
+        let mut fields = Map::new();
+
+        match name {
+            "load_rom" => {
+                let path = command.get("path").and_then(Value::as_str).ok_or("missing \"path\"")?;
+                return Ok(Some(Request::LoadRom(PathBuf::from(path))));
+            },
+            "pause" => return Ok(Some(Request::Pause(true))),
+            "resume" => return Ok(Some(Request::Pause(false))),
+            "step" => {
+                let frames = match command.get("frames") {
+                    Some(frames) => frames.as_u64().and_then(|frames| u32::try_from(frames).ok()).ok_or("\"frames\" must be a positive number")?,
+                    None => 1,
+                };
+                return Ok(Some(Request::Step(frames)));
+            },
+            "quit" => return Ok(Some(Request::Quit)),
+            "reset" => emulation.reset(),
+            "press" | "release" => {
+                let key = command.get("key").and_then(Value::as_u64).filter(|key| *key < 16).ok_or("\"key\" must be 0 to 15")?;
+                self.keys[key as usize] = name == "press";
+            },
+            "registers" => {
+                let chip = &emulation.chip8_data;
+                fields.insert("v".to_string(), json!(chip.var_registers));
+                fields.insert("i".to_string(), json!(chip.index));
+                fields.insert("pc".to_string(), json!(chip.pc));
+                fields.insert("stack".to_string(), json!(chip.stack));
+                fields.insert("dt".to_string(), json!(chip.delay_timer));
+                fields.insert("st".to_string(), json!(chip.sound_timer));
+            },
+            "memory" => {
+                let memory = &emulation.chip8_data.memory;
+                let address = command.get("address").and_then(Value::as_u64).ok_or("missing \"address\"")? as usize;
+                let length = command.get("length").and_then(Value::as_u64).unwrap_or(1) as usize;
+                let bytes = address.checked_add(length)
+                    .and_then(|end| memory.get(address..end))
+                    .ok_or_else(|| format!("memory ends at {:#x}", memory.len()))?;
+                fields.insert("data".to_string(), json!(hex::encode(bytes)));
+            },
+            // Pixels are row by row, each one the bitplanes lit at it
+            "framebuffer" => {
+                let display = &emulation.display;
+                fields.insert("width".to_string(), json!(display.width));
+                fields.insert("height".to_string(), json!(display.height));
+                fields.insert("pixels".to_string(), json!(display.pixels()));
+            },
+            "save_state" => {
+                fields.insert("state".to_string(), json!(hex::encode(emulation.save_state())));
+            },
+            "load_state" => {
+                let state = command.get("state").and_then(Value::as_str).ok_or("missing \"state\"")?;
+                let state = hex::decode(state).map_err(|err| format!("\"state\" is not hex: {}", err))?;
+                emulation.load_state(&state)?;
+            },
+            _ => return Err(format!("unknown command `{}`", name)),
+        }
+
+        self.reply(Ok(fields));
+        Ok(None)
+    }
+
+}
+
+// The reply to `quit` and anything else still unsent get a last chance to go out
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        let Some(client) = self.client.as_mut() else {
+            return;
+        };
+        if client.set_nonblocking(false).is_ok() && client.set_write_timeout(Some(CLOSE_TIMEOUT)).is_ok() {
+            let _ = client.write_all(&self.unsent);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::{BufRead, BufReader}, thread};
+
+    use super::*;
+    use crate::emulation::quirks::Platform;
+
+    #[test]
+    fn replies_wait_for_a_full_socket() {
+        let mut server = ControlServer::listen(0).unwrap();
+        let port = server.listener.local_addr().unwrap().port();
+        let client = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        let mut emulation = Emulation::new(Platform::Chip8, 0);
+        while server.client.is_none() {
+            server.next_request(&mut emulation);
+        }
+
+        // Nothing reads until the kernel buffers are full and replies start queueing up
+        let mut fields = Map::new();
+        fields.insert("data".to_string(), json!("x".repeat(64 * 1024)));
+        let mut sent = 0;
+        while server.unsent.is_empty() {
+            server.reply(Ok(fields.clone()));
+            sent += 1;
+        }
+        assert!(server.client.is_some());
+
+        let reader = thread::spawn(move || BufReader::new(client).lines().take(sent).count());
+        while !server.unsent.is_empty() {
+            server.next_request(&mut emulation);
+        }
+        assert_eq!(reader.join().unwrap(), sent);
+    }
+}
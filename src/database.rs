@@ -0,0 +1,179 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use serde::Deserialize;
+
+use crate::{config::Config, emulation::quirks::{Platform, Quirks}, palette::Palette};
+
+// Reads the community chip-8-database (https://github.com/chip-8/chip-8-database):
+// `sha1-hashes.json` maps a ROM hash to an index into the `programs.json` array.
+
+#[derive(Deserialize)]
+struct Program {
+    title: String,
+    roms: HashMap<String, RomEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RomEntry {
+    #[serde(default)]
+    platforms: Vec<String>,
+    tickrate: Option<u32>,
+    #[serde(default)]
+    keys: HashMap<String, u8>,
+    colors: Option<Colors>,
+    #[serde(default)]
+    quirky_platforms: HashMap<String, HashMap<String, bool>>,
+}
+
+#[derive(Deserialize)]
+struct Colors {
+    #[serde(default)]
+    pixels: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct RomSettings {
+    pub title: Option<String>,
+    pub platform: Option<Platform>,
+    // Overrides on top of the platform's quirks, by `Quirks::set` name
+    pub quirks: Vec<(String, bool)>,
+    pub instructions_per_frame: Option<u32>,
+    pub palette: Option<Palette>,
+    // Game actions such as `up` or `a` mapped to keypad keys
+    pub keys: Vec<(String, u8)>,
+}
+
+impl RomSettings {
+
+    // A missing database just means no settings are found
+    pub fn lookup(database_dir: &str, hash: &str) -> Result<Self, String> {
+        let hashes_path = Path::new(database_dir).join("sha1-hashes.json");
+        let programs_path = Path::new(database_dir).join("programs.json");
+        if !hashes_path.is_file() || !programs_path.is_file() {
+            return Ok(Self::default());
+        }
+
+        let hashes: HashMap<String, usize> = read_json(&hashes_path)?;
+        let Some(index) = hashes.get(hash) else {
+            return Ok(Self::default());
+        };
+
+        let mut programs: Vec<Program> = read_json(&programs_path)?;
+        if *index >= programs.len() {
+            return Err(format!("{} points past the end of {}", hash, programs_path.display()));
+        }
+        let program = programs.swap_remove(*index);
+        let Some(rom) = program.roms.get(hash) else {
+            return Ok(Self {
+                title: Some(program.title),
+                ..Self::default()
+            });
+        };
+
+        let (platform_id, platform) = rom.platforms.iter()
+            .find_map(|id| platform_from_database(id).map(|platform| (id, platform)))
+            .unzip();
+
+        let quirks = platform_id
+            .and_then(|id| rom.quirky_platforms.get(id))
+            .map(|quirks| quirks.iter().filter_map(|(name, value)| quirk_from_database(name, *value)).collect())
+            .unwrap_or_default();
+
+        let palette = rom.colors.as_ref().and_then(|colors| {
+            let pixels: Vec<&str> = colors.pixels.iter().map(|color| color.as_str()).collect();
+            Palette::from_colors(&program.title, &pixels).ok()
+        });
+
+        Ok(Self {
+            title: Some(program.title),
+            platform,
+            quirks,
+            instructions_per_frame: rom.tickrate,
+            palette,
+            keys: rom.keys.iter().map(|(action, key)| (action.clone(), *key)).collect(),
+        })
+    }
+
+    // Applies the `[settings]` and `[quirks]` sections of a per-ROM override file
+    pub fn apply_config(&mut self, config: &Config) -> Result<(), String> {
+        for (key, value) in config.section("settings") {
+            match key {
+                "title" => self.title = Some(value.to_string()),
+                "platform" => {
+                    self.platform = Some(Platform::from_name(value)
+                        .ok_or_else(|| format!("unknown platform `{}`", value))?);
+                },
+                "ipf" => {
+                    self.instructions_per_frame = Some(value.parse()
+                        .map_err(|_| format!("ipf expects a number, got `{}`", value))?);
+                },
+                "palette" => self.palette = Some(Palette::parse(value)?),
+                _ => return Err(format!("unknown setting `{}`", key)),
+            }
+        }
+
+        for (key, value) in config.section("quirks") {
+            let value = value.parse()
+                .map_err(|_| format!("quirk `{}` expects true or false, got `{}`", key, value))?;
+            self.quirks.push((key.to_string(), value));
+        }
+
+        Ok(())
+    }
+
+    pub fn apply_quirks(&self, quirks: &mut Quirks) -> Result<(), String> {
+        for (name, value) in &self.quirks {
+            quirks.set(name, *value)?;
+        }
+        Ok(())
+    }
+
+}
+
+// Titles for a batch of ROM hashes, reading the database once. A missing database knows no titles.
+pub fn titles(database_dir: &str, hashes: &[String]) -> Result<Vec<Option<String>>, String> {
+    let hashes_path = Path::new(database_dir).join("sha1-hashes.json");
+    let programs_path = Path::new(database_dir).join("programs.json");
+    if !hashes_path.is_file() || !programs_path.is_file() {
+        return Ok(vec![None; hashes.len()]);
+    }
+
+    let indices: HashMap<String, usize> = read_json(&hashes_path)?;
+    let programs: Vec<Program> = read_json(&programs_path)?;
+    Ok(hashes.iter()
+        .map(|hash| indices.get(hash).and_then(|index| programs.get(*index)).map(|program| program.title.clone()))
+        .collect())
+}
+
+pub fn rom_hash(rom: &[u8]) -> String {
+    sha1_smol::Sha1::from(rom).digest().to_string()
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<T, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    serde_json::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+fn platform_from_database(id: &str) -> Option<Platform> {
+    match id {
+        "originalChip8" | "hybridVIP" => Some(Platform::Chip8),
+        "modernChip8" => Some(Platform::ModernChip8),
+        "chip48" | "superchip1" | "superchip" => Some(Platform::SuperChip),
+        "xochip" => Some(Platform::XoChip),
+        _ => None,
+    }
+}
+
+fn quirk_from_database(name: &str, value: bool) -> Option<(String, bool)> {
+    let (quirk, value) = match name {
+        "logic" => ("vf_reset", value),
+        "memoryLeaveIUnchanged" => ("memory_increment", !value),
+        "vblank" => ("display_wait", value),
+        "wrap" => ("clipping", !value),
+        "shift" => ("shift_vx", value),
+        "jump" => ("jump_vx", value),
+        _ => return None,
+    };
+    Some((quirk.to_string(), value))
+}
@@ -0,0 +1,215 @@
+use crate::{emulation::Emulation, frontend::{DebugCommand, Hotkey}, palette::Rgb, text};
+
+use self::{cheats::CheatView, memory::MemoryView};
+
+pub use self::memory::CursorMove;
+
+mod cheats;
+mod memory;
+
+// The debug panel has three pages. The CPU page shows registers, stack and a disassembly
+// that follows PC, clicking a disassembly line toggles a breakpoint there. The memory
+// page is a hex editor, see `memory`, and the cheats page does RAM search, see `cheats`.
+// The buttons on top step, resume and switch pages.
+
+const COLUMNS: usize = 40;
+const ROWS: usize = 40;
+pub const WIDTH: usize = COLUMNS * text::CELL_WIDTH;
+pub const HEIGHT: usize = ROWS * text::CELL_HEIGHT;
+
+const STACK_COLUMN: usize = 16;
+const DISASSEMBLY_TOP: usize = 14;
+// Lines of disassembly shown above the current instruction
+const LINES_BEFORE_PC: usize = 8;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Page {
+    Cpu,
+    Memory,
+    Cheats,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Button {
+    Hotkey(Hotkey),
+    Page(Page),
+}
+
+// Label, first text column and what clicking it does
+const BUTTONS: [(&str, usize, Button); 6] = [
+    ("STEP", 0, Button::Hotkey(Hotkey::Step)),
+    ("FRAME", 6, Button::Hotkey(Hotkey::FrameAdvance)),
+    ("RUN/PAUSE", 13, Button::Hotkey(Hotkey::Pause)),
+    ("CPU", 23, Button::Page(Page::Cpu)),
+    ("MEMORY", 27, Button::Page(Page::Memory)),
+    ("CHEATS", 34, Button::Page(Page::Cheats)),
+];
+
+const BACKGROUND: Rgb = (0x10, 0x10, 0x18);
+const FOREGROUND: Rgb = (0xE0, 0xE0, 0xE0);
+const DIM: Rgb = (0x80, 0x80, 0x90);
+const BUTTON: Rgb = (0x30, 0x30, 0x40);
+const CURRENT: Rgb = (0x30, 0x60, 0xC0);
+const BREAKPOINT: Rgb = (0xE0, 0x40, 0x40);
+
+pub enum Click {
+    Hotkey(Hotkey),
+    Command(DebugCommand),
+}
+
+pub struct DebugPanel {
+    // Memory can only be edited while paused
+    pub paused: bool,
+    page: Page,
+    memory: MemoryView,
+    cheats: CheatView,
+    frame: Vec<Rgb>,
+    // Address on each disassembly line as last drawn, to map clicks back
+    lines: Vec<u16>,
+}
+
+impl DebugPanel {
+
+    pub fn new() -> Self {
+        Self {
+            paused: false,
+            page: Page::Cpu,
+            memory: MemoryView::new(),
+            cheats: CheatView::new(),
+            frame: vec![BACKGROUND; WIDTH * HEIGHT],
+            lines: Vec::new(),
+        }
+    }
+
+    pub fn draw(&mut self, emulation: &Emulation) -> &[Rgb] {
+        let chip = &emulation.chip8_data;
+        self.frame.fill(BACKGROUND);
+
+        for (label, column, button) in BUTTONS {
+            let x = column * text::CELL_WIDTH;
+            let color = if button == Button::Page(self.page) { CURRENT } else { BUTTON };
+            text::fill_rect(&mut self.frame, WIDTH, x, 0, label.len() * text::CELL_WIDTH + 1, text::CELL_HEIGHT, color);
+            text::draw(&mut self.frame, WIDTH, x + 1, 1, label, FOREGROUND);
+        }
+
+        match self.page {
+            Page::Cpu => {},
+            Page::Memory => {
+                if !self.paused {
+                    self.memory.stop_editing();
+                }
+                self.memory.draw(&mut self.frame, emulation);
+                return &self.frame;
+            },
+            Page::Cheats => {
+                self.cheats.draw(&mut self.frame, emulation);
+                return &self.frame;
+            },
+        }
+
+        for i in 0..8 {
+            let line = format!("V{:X} {:02x}  V{:X} {:02x}", i, chip.var_registers[i], i + 8, chip.var_registers[i + 8]);
+            self.line(0, 2 + i, &line, FOREGROUND);
+        }
+        self.line(0, 10, &format!("PC {:03x}  I {:03x}", chip.pc, chip.index), FOREGROUND);
+        self.line(0, 11, &format!("DT {:02x}   ST {:02x}", chip.delay_timer, chip.sound_timer), FOREGROUND);
+
+        self.line(STACK_COLUMN, 2, &format!("STACK ({})", chip.stack.len()), DIM);
+        for (depth, address) in chip.stack.iter().enumerate() {
+            let (row, column) = (3 + depth / 4, STACK_COLUMN + depth % 4 * 6);
+            self.line(column, row, &format!("{:x}:{:03x}", depth, address), FOREGROUND);
+        }
+
+        let header_y = (DISASSEMBLY_TOP - 1) * text::CELL_HEIGHT;
+        text::fill_rect(&mut self.frame, WIDTH, 0, header_y, WIDTH, text::CELL_HEIGHT, BUTTON);
+        self.line(0, DISASSEMBLY_TOP - 1, "DISASSEMBLY", DIM);
+
+        // Lines stay two bytes apart from PC, the last one has to fit in memory
+        let last = chip.memory.len() - 2;
+        let pc = chip.pc as usize;
+        let first = pc.saturating_sub(LINES_BEFORE_PC * 2).min(last);
+        self.lines = (first..=last).step_by(2).take(ROWS - DISASSEMBLY_TOP).map(|address| address as u16).collect();
+
+        for (index, address) in self.lines.clone().into_iter().enumerate() {
+            let row = DISASSEMBLY_TOP + index;
+            if address as usize == pc {
+                text::fill_rect(&mut self.frame, WIDTH, 0, row * text::CELL_HEIGHT, WIDTH, text::CELL_HEIGHT, CURRENT);
+            }
+            if emulation.breakpoints.contains(&address) {
+                self.line(0, row, "*", BREAKPOINT);
+            }
+            let address = address as usize;
+            let line = format!("{:03x}  {}  {}", address, emulation.fetch(address), emulation.disassemble(address));
+            self.line(1, row, &line, FOREGROUND);
+        }
+
+        &self.frame
+    }
+
+    // `x` and `y` are in panel pixels
+    pub fn click(&mut self, x: i32, y: i32) -> Option<Click> {
+        if x < 0 || y < 0 {
+            return None;
+        }
+        let (column, row) = (x as usize / text::CELL_WIDTH, y as usize / text::CELL_HEIGHT);
+
+        if row == 0 {
+            let button = BUTTONS.iter()
+                .find(|(label, first, _)| (*first..first + label.len()).contains(&column))
+                .map(|(_, _, button)| *button)?;
+            return match button {
+                Button::Hotkey(hotkey) => Some(Click::Hotkey(hotkey)),
+                Button::Page(page) => {
+                    self.page = page;
+                    None
+                },
+            };
+        }
+        match self.page {
+            Page::Cpu => {},
+            Page::Memory => {
+                if self.paused {
+                    self.memory.click(column, row);
+                }
+                return None;
+            },
+            Page::Cheats => return self.cheats.click(column, row).map(Click::Command),
+        }
+        let line = row.checked_sub(DISASSEMBLY_TOP)?;
+        self.lines.get(line).map(|address| Click::Command(DebugCommand::ToggleBreakpoint(*address)))
+    }
+
+    // Mouse wheel notches, positive scrolls towards higher addresses
+    pub fn scroll(&mut self, notches: i32) {
+        if self.page == Page::Memory {
+            self.memory.scroll(notches as isize * 3);
+        }
+    }
+
+    pub fn move_cursor(&mut self, movement: CursorMove) {
+        if self.page == Page::Memory {
+            self.memory.move_cursor(movement);
+        }
+    }
+
+    pub fn type_text(&mut self, text: &str) -> Vec<DebugCommand> {
+        match self.page {
+            Page::Memory if self.paused => text.chars()
+                .filter_map(|c| self.memory.type_char(c))
+                .map(|(address, value)| DebugCommand::WriteMemory(address, value))
+                .collect(),
+            Page::Cheats => text.chars().filter_map(|c| self.cheats.type_char(c)).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn line(&mut self, column: usize, row: usize, line: &str, color: Rgb) {
+        draw_line(&mut self.frame, column, row, line, color);
+    }
+
+}
+
+// Text on the panel's character grid
+fn draw_line(frame: &mut [Rgb], column: usize, row: usize, line: &str, color: Rgb) {
+    text::draw(frame, WIDTH, column * text::CELL_WIDTH + 1, row * text::CELL_HEIGHT + 1, line, color);
+}
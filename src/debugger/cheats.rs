@@ -0,0 +1,132 @@
+use crate::{
+    emulation::{cheats::Comparison, Emulation},
+    frontend::DebugCommand,
+    palette::Rgb,
+    text,
+};
+
+use super::{draw_line, BUTTON, CURRENT, DIM, FOREGROUND, ROWS, WIDTH};
+
+// The cheats page of the debugger: RAM search on top, clicking a candidate freezes it.
+// Below are the active cheats, click one to type a new value or its X to remove it.
+
+// Label, first text column and the search step it runs, None starts over
+const SEARCH_BUTTONS: [(&str, usize, Option<Comparison>); 5] = [
+    ("NEW", 0, None),
+    ("EQUAL", 4, Some(Comparison::Equal)),
+    ("CHANGED", 10, Some(Comparison::Changed)),
+    ("UP", 18, Some(Comparison::Increased)),
+    ("DOWN", 21, Some(Comparison::Decreased)),
+];
+const SEARCH_ROW: usize = 2;
+const CANDIDATES_TOP: usize = 5;
+const MAX_CANDIDATES: usize = 16;
+const CHEATS_TOP: usize = CANDIDATES_TOP + MAX_CANDIDATES + 2;
+const REMOVE_COLUMN: usize = 12;
+
+pub struct CheatView {
+    // Cheat whose value is being typed and the first hex digit typed for it
+    selected: Option<u16>,
+    high_nibble: Option<u8>,
+    // Address on each line as last drawn, to map clicks back
+    candidates: Vec<u16>,
+    cheats: Vec<u16>,
+}
+
+impl CheatView {
+
+    pub fn new() -> Self {
+        Self {
+            selected: None,
+            high_nibble: None,
+            candidates: Vec::new(),
+            cheats: Vec::new(),
+        }
+    }
+
+    pub fn draw(&mut self, frame: &mut [Rgb], emulation: &Emulation) {
+        let memory = &emulation.chip8_data.memory;
+
+        for (label, column, _) in SEARCH_BUTTONS {
+            let (x, y) = (column * text::CELL_WIDTH, SEARCH_ROW * text::CELL_HEIGHT);
+            text::fill_rect(frame, WIDTH, x, y, label.len() * text::CELL_WIDTH + 1, text::CELL_HEIGHT, BUTTON);
+            draw_line(frame, column, SEARCH_ROW, label, FOREGROUND);
+        }
+
+        self.candidates.clear();
+        match &emulation.ram_search {
+            None => draw_line(frame, 0, SEARCH_ROW + 1, "NEW STARTS A SEARCH", DIM),
+            Some(search) => {
+                draw_line(frame, 0, SEARCH_ROW + 1, &format!("{} CANDIDATES", search.candidates.len()), DIM);
+                draw_line(frame, 0, CANDIDATES_TOP - 1, "ADDR WAS NOW  CLICK TO FREEZE", DIM);
+                self.candidates.extend(search.candidates.iter().take(MAX_CANDIDATES));
+                for (index, address) in self.candidates.iter().enumerate() {
+                    let line = format!("{:03x}  {:02x}  {:02x}", address, search.previous(*address), memory[*address as usize]);
+                    draw_line(frame, 0, CANDIDATES_TOP + index, &line, FOREGROUND);
+                }
+                if search.candidates.len() > MAX_CANDIDATES {
+                    let more = format!("{} MORE", search.candidates.len() - MAX_CANDIDATES);
+                    draw_line(frame, 0, CANDIDATES_TOP + MAX_CANDIDATES, &more, DIM);
+                }
+            },
+        }
+
+        let header_y = (CHEATS_TOP - 1) * text::CELL_HEIGHT;
+        text::fill_rect(frame, WIDTH, 0, header_y, WIDTH, text::CELL_HEIGHT, BUTTON);
+        draw_line(frame, 0, CHEATS_TOP - 1, "CHEATS  CLICK TO EDIT", DIM);
+
+        // A cheat removed some other way can't stay selected
+        if !emulation.cheats.iter().any(|cheat| Some(cheat.address) == self.selected) {
+            self.selected = None;
+            self.high_nibble = None;
+        }
+        self.cheats = emulation.cheats.iter().take(ROWS - CHEATS_TOP).map(|cheat| cheat.address).collect();
+        for (index, cheat) in emulation.cheats.iter().take(self.cheats.len()).enumerate() {
+            let row = CHEATS_TOP + index;
+            if self.selected == Some(cheat.address) {
+                text::fill_rect(frame, WIDTH, 0, row * text::CELL_HEIGHT, REMOVE_COLUMN * text::CELL_WIDTH, text::CELL_HEIGHT, CURRENT);
+            }
+            let value = match (self.selected, self.high_nibble) {
+                (Some(selected), Some(high)) if selected == cheat.address => format!("{:x}_", high),
+                _ => format!("{:02x}", cheat.value),
+            };
+            draw_line(frame, 0, row, &format!("{:03x} = {}", cheat.address, value), FOREGROUND);
+            draw_line(frame, REMOVE_COLUMN, row, "X", DIM);
+        }
+    }
+
+    // Text cell coordinates
+    pub fn click(&mut self, column: usize, row: usize) -> Option<DebugCommand> {
+        if row == SEARCH_ROW {
+            return SEARCH_BUTTONS.iter()
+                .find(|(label, first, _)| (*first..first + label.len()).contains(&column))
+                .map(|(_, _, comparison)| DebugCommand::Search(*comparison));
+        }
+        if let Some(address) = row.checked_sub(CANDIDATES_TOP).and_then(|line| self.candidates.get(line)) {
+            return Some(DebugCommand::Freeze(*address));
+        }
+
+        let address = *row.checked_sub(CHEATS_TOP).and_then(|line| self.cheats.get(line))?;
+        self.high_nibble = None;
+        if column == REMOVE_COLUMN {
+            self.selected = None;
+            return Some(DebugCommand::RemoveCheat(address));
+        }
+        self.selected = Some(address);
+        None
+    }
+
+    // Hex digits typed for the selected cheat, returns the new value once both are in
+    pub fn type_char(&mut self, c: char) -> Option<DebugCommand> {
+        let address = self.selected?;
+        let digit = c.to_digit(16)? as u8;
+        let Some(high) = self.high_nibble.take() else {
+            self.high_nibble = Some(digit);
+            return None;
+        };
+
+        self.selected = None;
+        Some(DebugCommand::SetCheat(address, high << 4 | digit))
+    }
+
+}
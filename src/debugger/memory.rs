@@ -0,0 +1,185 @@
+use crate::{
+    emulation::{chip::FONT_END, Emulation, PROGRAM_START},
+    palette::Rgb,
+    text,
+};
+
+use super::{draw_line, BACKGROUND, CURRENT, DIM, FOREGROUND, ROWS, WIDTH};
+
+// The memory page of the debugger: a hex dump of all of memory coloured by region,
+// with bytes changed by the last instructions highlighted and the sprite at I drawn out
+
+const BYTES_PER_ROW: usize = 8;
+const TOP: usize = 2;
+const VISIBLE_ROWS: usize = ROWS - TOP;
+// First text column of the hex bytes, after the address
+const BYTES_COLUMN: usize = 5;
+const SPRITE_COLUMN: usize = 31;
+const SPRITE_BYTES: usize = 16;
+const SPRITE_SCALE: usize = 4;
+
+const FONT: Rgb = (0xE0, 0xC0, 0x40);
+const WORK: Rgb = (0x60, 0xC0, 0xC0);
+const CHANGED: Rgb = (0x80, 0x20, 0x20);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CursorMove {
+    Left,
+    Right,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+}
+
+pub struct MemoryView {
+    // First row of bytes shown
+    scroll: usize,
+    // Byte being edited and the first hex digit typed for it
+    cursor: Option<usize>,
+    high_nibble: Option<u8>,
+    // Memory as of the last time instructions ran, to find what they changed
+    previous: Vec<u8>,
+    changed: Vec<bool>,
+    executed: u64,
+}
+
+impl MemoryView {
+
+    pub fn new() -> Self {
+        Self {
+            scroll: PROGRAM_START / BYTES_PER_ROW,
+            cursor: None,
+            high_nibble: None,
+            previous: Vec::new(),
+            changed: Vec::new(),
+            executed: 0,
+        }
+    }
+
+    pub fn draw(&mut self, frame: &mut [Rgb], emulation: &Emulation) {
+        let memory = &emulation.chip8_data.memory;
+        if self.previous.len() != memory.len() {
+            self.previous = memory.to_vec();
+            self.changed = vec![false; memory.len()];
+        }
+        if emulation.instructions_executed != self.executed {
+            for (address, byte) in memory.iter().enumerate() {
+                self.changed[address] = self.previous[address] != *byte;
+            }
+            self.previous.copy_from_slice(memory);
+            self.executed = emulation.instructions_executed;
+        }
+
+        let program_end = PROGRAM_START + emulation.program_len();
+        draw_line(frame, 0, 1, "FONT", FONT);
+        draw_line(frame, 5, 1, "PROGRAM", FOREGROUND);
+        draw_line(frame, 13, 1, "WORK", WORK);
+
+        let rows = memory.len() / BYTES_PER_ROW;
+        self.scroll = self.scroll.min(rows - VISIBLE_ROWS);
+        for row in 0..VISIBLE_ROWS {
+            let start = (self.scroll + row) * BYTES_PER_ROW;
+            draw_line(frame, 0, TOP + row, &format!("{:03x}", start), DIM);
+
+            for (offset, byte) in memory[start..start + BYTES_PER_ROW].iter().enumerate() {
+                let address = start + offset;
+                let column = BYTES_COLUMN + offset * 3;
+                let (x, y) = (column * text::CELL_WIDTH, (TOP + row) * text::CELL_HEIGHT);
+
+                if self.cursor == Some(address) {
+                    text::fill_rect(frame, WIDTH, x, y, 2 * text::CELL_WIDTH + 1, text::CELL_HEIGHT, CURRENT);
+                } else if self.changed[address] {
+                    text::fill_rect(frame, WIDTH, x, y, 2 * text::CELL_WIDTH + 1, text::CELL_HEIGHT, CHANGED);
+                }
+
+                let color = match address {
+                    _ if address < FONT_END => FONT,
+                    _ if address < PROGRAM_START => DIM,
+                    _ if address < program_end => FOREGROUND,
+                    _ => WORK,
+                };
+                let hex = match (self.cursor, self.high_nibble) {
+                    (Some(cursor), Some(high)) if cursor == address => format!("{:x}_", high),
+                    _ => format!("{:02x}", byte),
+                };
+                draw_line(frame, column, TOP + row, &hex, color);
+            }
+        }
+
+        let index = emulation.chip8_data.index as usize;
+        draw_line(frame, SPRITE_COLUMN, TOP, &format!("I {:03x}", index), DIM);
+        let left = SPRITE_COLUMN * text::CELL_WIDTH + 1;
+        let top = (TOP + 1) * text::CELL_HEIGHT + 1;
+        text::fill_rect(frame, WIDTH, left - 1, top - 1, 8 * SPRITE_SCALE + 2, SPRITE_BYTES * SPRITE_SCALE + 2, DIM);
+        text::fill_rect(frame, WIDTH, left, top, 8 * SPRITE_SCALE, SPRITE_BYTES * SPRITE_SCALE, BACKGROUND);
+        for (row, byte) in memory.iter().skip(index).take(SPRITE_BYTES).enumerate() {
+            for column in 0..8 {
+                if byte & (0x80 >> column) != 0 {
+                    let (x, y) = (left + column * SPRITE_SCALE, top + row * SPRITE_SCALE);
+                    text::fill_rect(frame, WIDTH, x, y, SPRITE_SCALE, SPRITE_SCALE, FOREGROUND);
+                }
+            }
+        }
+    }
+
+    // Text cell coordinates, picks the byte to edit
+    pub fn click(&mut self, column: usize, row: usize) {
+        let Some(row) = row.checked_sub(TOP).filter(|row| *row < VISIBLE_ROWS) else {
+            return;
+        };
+        let Some(offset) = column.checked_sub(BYTES_COLUMN).map(|column| column / 3).filter(|offset| *offset < BYTES_PER_ROW) else {
+            self.cursor = None;
+            return;
+        };
+        self.cursor = Some((self.scroll + row) * BYTES_PER_ROW + offset);
+        self.high_nibble = None;
+    }
+
+    pub fn scroll(&mut self, rows: isize) {
+        self.scroll = self.scroll.saturating_add_signed(rows);
+    }
+
+    pub fn move_cursor(&mut self, movement: CursorMove) {
+        let Some(cursor) = self.cursor else {
+            return;
+        };
+        let step: isize = match movement {
+            CursorMove::Left => -1,
+            CursorMove::Right => 1,
+            CursorMove::Up => -(BYTES_PER_ROW as isize),
+            CursorMove::Down => BYTES_PER_ROW as isize,
+            CursorMove::PageUp => -((VISIBLE_ROWS * BYTES_PER_ROW) as isize),
+            CursorMove::PageDown => (VISIBLE_ROWS * BYTES_PER_ROW) as isize,
+        };
+        let cursor = cursor.saturating_add_signed(step).min(self.previous.len().saturating_sub(1));
+        self.cursor = Some(cursor);
+        self.high_nibble = None;
+
+        let row = cursor / BYTES_PER_ROW;
+        if row < self.scroll {
+            self.scroll = row;
+        } else if row >= self.scroll + VISIBLE_ROWS {
+            self.scroll = row + 1 - VISIBLE_ROWS;
+        }
+    }
+
+    // Hex digits typed at the cursor, returns the byte to write once both are in
+    pub fn type_char(&mut self, c: char) -> Option<(u16, u8)> {
+        let cursor = self.cursor?;
+        let digit = c.to_digit(16)? as u8;
+        let Some(high) = self.high_nibble.take() else {
+            self.high_nibble = Some(digit);
+            return None;
+        };
+
+        self.move_cursor(CursorMove::Right);
+        Some((cursor as u16, high << 4 | digit))
+    }
+
+    pub fn stop_editing(&mut self) {
+        self.cursor = None;
+        self.high_nibble = None;
+    }
+
+}
@@ -1,98 +1,181 @@
-mod chip;
+pub mod cheats;
+pub mod chip;
+pub mod disassembler;
+pub mod display;
+pub mod instruction;
+pub mod quirks;
+pub mod script;
+pub mod sound;
+mod state;
 
-use std::thread;
-use std::time;
-use hex;
-use substring::Substring;
-use num::Num;
 use rand::prelude::*;
-use std::{ fs::{File, self}, io::Read };
-
-use crate::sdl::{canvas::CanvasUtils, events::EventHandler};
-
-fn decode_hex<T>(string: &str) -> T
-where 
-    T: Num,
-{
-    let Ok(num) = T::from_str_radix(string, 16) else {
-        panic!("Error parsing hex to decimal");
-    };
-    num
-}
+use std::collections::BTreeSet;
+use self::{cheats::{Cheat, RamSearch}, display::Display, instruction::Instruction, quirks::{Platform, Quirks}, script::ScriptState};
 
-fn least_significant_bit(num: u8) -> u8 {
-    let binary_str = format!("{:08b}", num);
-    binary_str.chars().nth(7).unwrap().to_digit(2).unwrap() as u8
-}
+pub const PROGRAM_START: usize = 0x200;
+// Deepest the call stack gets, 2NNN past it is ignored and save states hold this many
+pub use state::STACK_SLOTS;
 
-fn most_significant_bit(num: u8) -> u8 {
-    let binary_str = format!("{:08b}", num);
-    binary_str.chars().nth(0).unwrap().to_digit(2).unwrap() as u8
+pub struct Emulation {
+    instructions: Vec<u8>,
+    pub chip8_data: chip::Chip8Components,
+    pub display: Display,
+    pub keypad: [bool; 16],
+    pub quirks: Quirks,
+    pub trace: bool,
+    // Addresses that stop `run_frame` before the instruction there executes
+    pub breakpoints: BTreeSet<u16>,
+    // Set when a breakpoint stopped the last frame, so resuming runs that instruction instead of stopping again
+    at_breakpoint: bool,
+    // Written back at the start of every frame, see `cheats`
+    pub cheats: Vec<Cheat>,
+    pub ram_search: Option<RamSearch>,
+    // The attached script and what it has set up, see `script`
+    scripting: ScriptState,
+    // Running total across resets and ROM changes, for measuring speed
+    pub instructions_executed: u64,
+    // CXNN reseeds a StdRng from this each time so the generator can go in save states
+    rng_state: u64,
+    // XO-CHIP bitplanes selected by FN01 for drawing and clearing
+    planes: u8,
+    // Key pressed during FX0A, the instruction completes once it is released
+    key_wait: Option<u8>,
+    // Set by DXYN when the display wait quirk ends the frame early
+    vblank_wait: bool,
 }
 
-fn add_overflow(num: u8, add: u8) -> (bool, u8) {
-    let product = num as u16 + add as u16;
-    if product > 255 {
-        (true, (product-256) as u8)
-    } else {
-        (false, product as u8)
+impl Emulation {
+
+    // Starts with no program loaded, see `load_rom`
+    pub fn new(platform: Platform, seed: u64) -> Self {
+        let mut emulation = Self {
+            instructions: Vec::new(),
+            chip8_data: chip::Chip8Components::new(),
+            display: Display::new(),
+            keypad: [false; 16],
+            quirks: platform.quirks(),
+            trace: false,
+            breakpoints: BTreeSet::new(),
+            at_breakpoint: false,
+            cheats: Vec::new(),
+            ram_search: None,
+            scripting: ScriptState::default(),
+            instructions_executed: 0,
+            rng_state: seed,
+            planes: 1,
+            key_wait: None,
+            vblank_wait: false,
+        };
+        emulation.reset();
+        emulation
     }
-}
 
-fn sub_overflow(num: u8, sub: u8) -> (bool, u8) {
-    let product = num as i16 - sub as i16;
-    if product < 0 {
-        (false, (num as i16 + 256 - sub as i16) as u8)
-    } else {
-        (true, product as u8)
+    // Replaces the program and resets the machine, the current one keeps running if the ROM doesn't fit
+    pub fn load_rom(&mut self, rom: &[u8]) -> Result<(), String> {
+        let capacity = self.chip8_data.memory.len() - PROGRAM_START;
+        if rom.is_empty() {
+            return Err("the ROM is empty".to_string());
+        }
+        if rom.len() > capacity {
+            return Err(format!("the ROM is {} bytes, at most {} fit in memory", rom.len(), capacity));
+        }
+
+        self.instructions = rom.to_vec();
+        self.reset();
+        Ok(())
     }
-}
 
-trait NumExt {
-    fn to_binary_iterator(&self) -> Vec<u32>;
-}
+    // Size of the loaded ROM, which sits at PROGRAM_START
+    pub fn program_len(&self) -> usize {
+        self.instructions.len()
+    }
 
-impl<T: Num + std::fmt::Binary> NumExt for T {
-    fn to_binary_iterator(&self) -> Vec<u32> {
-        let binary_str = format!("{:08b}", self);
-        binary_str.chars().map(|x| x.to_digit(2).unwrap() ).collect()
+    // Clears memory, registers and the display and reloads the program, quirks, breakpoints, cheats, scripts and the RNG carry on
+    pub fn reset(&mut self) {
+        self.chip8_data = chip::Chip8Components::new();
+        let program = PROGRAM_START..PROGRAM_START + self.instructions.len();
+        self.chip8_data.memory[program].copy_from_slice(&self.instructions);
+        self.chip8_data.pc = PROGRAM_START as u16;
+
+        self.display = Display::new();
+        self.keypad = [false; 16];
+        self.planes = 1;
+        self.key_wait = None;
+        self.vblank_wait = false;
+        self.at_breakpoint = false;
     }
-}
 
-pub struct Emulation<'a> {
-    instructions: Vec<u8>,
-    pub chip8_data: chip::Chip8Components,
-    pub canvas: &'a mut CanvasUtils,
-    events_handler: &'a mut EventHandler,
-}
+    // Runs one 60Hz frame worth of instructions and then ticks the timers.
+    // Returns true if a breakpoint stopped it partway, the timers are left alone then.
+    pub fn run_frame(&mut self, instructions_per_frame: u32) -> bool {
+        self.apply_cheats();
+        self.scripting.overlay.clear();
+        for (pressed, held) in self.keypad.iter_mut().zip(self.scripting.keys) {
+            *pressed |= held;
+        }
+        for _ in 0..instructions_per_frame {
+            if !self.at_breakpoint && self.breakpoints.contains(&self.chip8_data.pc) {
+                self.at_breakpoint = true;
+                return true;
+            }
+            self.step();
+            if self.vblank_wait {
+                break;
+            }
+        }
+        self.vblank_wait = false;
+        self.display.latch();
+
+        self.tick_timers();
+        self.call_script(|script, context| script.on_frame_end(context));
+        false
+    }
 
-impl<'a> Emulation<'a> {
-
-    pub fn new(
-        path: &str,
-        canvas: &'a mut CanvasUtils,
-        events_handler: &'a mut EventHandler,
-    ) -> Self {
-        let mut f = File::open(path).expect("File not found");
-        let metadata = fs::metadata(path).expect("Could not read metadata");
-        let mut instructions = vec![0; metadata.len() as usize];
-        
-        f.read(&mut instructions).expect("Buffer overflow");
-
-        let mut chip8_data =chip::Chip8Components::new();
-        for i in 0..instructions.len() {
-            chip8_data.memory[0x200 + i] = instructions[i];
+    // Executes a single instruction, also the one a breakpoint stopped at
+    pub fn step(&mut self) {
+        self.at_breakpoint = false;
+        let pc = self.chip8_data.pc;
+        if self.scripting.pc_hooks.contains(&pc) {
+            self.call_script(|script, context| script.on_pc(pc, context));
         }
 
-        chip8_data.pc = 0x200;
+        self.execute_next_instruction();
 
-        Self {
-            instructions,
-            chip8_data,
-            canvas,
-            events_handler,
+        for (address, value) in std::mem::take(&mut self.scripting.memory_writes) {
+            self.call_script(|script, context| script.on_memory_write(address, value, context));
         }
     }
+
+    // The instruction at `address` as hex text
+    pub fn fetch(&self, address: usize) -> String {
+        format!("{:04x}", self.opcode(address))
+    }
+
+    pub fn opcode(&self, address: usize) -> u16 {
+        u16::from_be_bytes([self.read_memory(address), self.read_memory(address + 1)])
+    }
+
+    // Addresses past the end of memory wrap around to the start, wherever I or PC points
+    fn read_memory(&self, address: usize) -> u8 {
+        self.chip8_data.memory[address % self.chip8_data.memory.len()]
+    }
+
+    pub fn disassemble(&self, address: usize) -> String {
+        disassembler::disassemble(self.opcode(address), &self.quirks)
+    }
+
+    pub fn tick_timers(&mut self) {
+        self.chip8_data.delay_timer = self.chip8_data.delay_timer.saturating_sub(1);
+        self.chip8_data.sound_timer = self.chip8_data.sound_timer.saturating_sub(1);
+    }
+
+    pub fn sound_playing(&self) -> bool {
+        self.chip8_data.sound_timer > 0
+    }
+
+    fn is_pressed(&self, key: u8) -> bool {
+        self.keypad[(key & 0xF) as usize]
+    }
     
     #[allow(dead_code)]
     pub fn debug_file(&self) {
@@ -102,282 +185,257 @@ impl<'a> Emulation<'a> {
     }
 
     pub fn execute_next_instruction(&mut self) {
-        self.events_handler.update_events();
-        //thread::sleep(time::Duration::from_millis(100));
-
         let current_pc = self.chip8_data.pc as usize;
-        let instruction_hex: String = hex::encode(&self.chip8_data.memory[current_pc..current_pc+2]);
-        let instruction_dec: u16 = decode_hex(&instruction_hex);
+        let opcode = self.opcode(current_pc);
 
         let mut jumped = false;
+        self.instructions_executed += 1;
+
+        if self.trace {
+            eprintln!(
+                "{:03x}: {:04x}  I={:03x} V={}",
+                current_pc,
+                opcode,
+                self.chip8_data.index,
+                hex::encode(self.chip8_data.var_registers)
+            );
+        }
 
-        //println!("Current Instuction: {}", instruction_hex);
-
-        match instruction_hex.chars().nth(0).expect("Error in instruction deconstruction") {
-            '0' => {
-                if instruction_dec == 0x00E0 {
-                    self.canvas.clear_screen();
-                } else {
-                    self.chip8_data.pc = self.chip8_data.stack.pop().expect("No item in stack");
+        let registers = &mut self.chip8_data.var_registers;
+        match Instruction::decode(opcode) {
+            Instruction::Clear => self.display.clear(self.planes),
+            Instruction::Lores => self.display.set_hires(false),
+            Instruction::Hires => self.display.set_hires(true),
+            // A return with nothing on the stack is ignored
+            Instruction::Return => {
+                if let Some(address) = self.chip8_data.stack.pop() {
+                    self.chip8_data.pc = address;
                 }
             },
-            '1' => {
-                //println!("Jumped to {}", decode_hex::<u16>(instruction_hex.substring(1, 4)));
-                self.chip8_data.pc = decode_hex(instruction_hex.substring(1, 4));
+            Instruction::Jump(nnn) => {
+                self.chip8_data.pc = nnn;
                 jumped = true;
             },
-            '2' => {
+            // A call with every stack slot in use is ignored, like a return with none.
+            // The call's own address is pushed, 00EE steps past it on return.
+            Instruction::Call(nnn) if self.chip8_data.stack.len() < state::STACK_SLOTS => {
                 self.chip8_data.stack.push(self.chip8_data.pc);
-                self.chip8_data.pc = decode_hex(instruction_hex.substring(1, 4));
+                self.chip8_data.pc = nnn;
+                jumped = true;
             },
-            '3' => {
-                let x = self.chip8_data.var_registers[
-                    decode_hex::<usize>(instruction_hex.substring(1, 2))
-                ];
-                let n = decode_hex(instruction_hex.substring(2, 4));
-
-                if x == n {
+            Instruction::Call(_) => {},
+            Instruction::SkipEqual(x, nn) => {
+                if registers[x] == nn {
                     self.chip8_data.pc += 2;
                 }
             },
-            '4' => {
-                let x = self.chip8_data.var_registers[
-                    decode_hex::<usize>(instruction_hex.substring(1, 2))
-                ];
-                let n = decode_hex(instruction_hex.substring(2, 4));
-
-                if x != n {
+            Instruction::SkipNotEqual(x, nn) => {
+                if registers[x] != nn {
+                    self.chip8_data.pc += 2;
+                }
+            },
+            Instruction::SkipEqualRegisters(x, y) => {
+                if registers[x] == registers[y] {
                     self.chip8_data.pc += 2;
                 }
             },
-            '5' => {
-                let x = self.chip8_data.var_registers[
-                    decode_hex::<usize>(instruction_hex.substring(1, 2))
-                ];
-                let y = self.chip8_data.var_registers[
-                    decode_hex::<usize>(instruction_hex.substring(2, 3))
-                ];
-
-                if x == y {
+            Instruction::SkipNotEqualRegisters(x, y) => {
+                if registers[x] != registers[y] {
                     self.chip8_data.pc += 2;
                 }
             },
-            '6' => {
-                self.chip8_data.var_registers[
-                    decode_hex::<usize>(instruction_hex.substring(1, 2))
-                ] = decode_hex(instruction_hex.substring(2, 4));
+            Instruction::Load(x, nn) => registers[x] = nn,
+            Instruction::Add(x, nn) => registers[x] = registers[x].wrapping_add(nn),
+            Instruction::Move(x, y) => registers[x] = registers[y],
+            // VF is set after VX in the arms below, so with X=F the flag is what's left
+            Instruction::Or(x, y) => {
+                registers[x] |= registers[y];
+                if self.quirks.vf_reset {
+                    registers[0xF] = 0;
+                }
             },
-            '7' => {
-                let val = add_overflow(decode_hex::<u8>(instruction_hex.substring(2, 4)),
-                    self.chip8_data.var_registers[
-                        decode_hex::<usize>(instruction_hex.substring(1, 2))
-                    ]
-                );
-
-                self.chip8_data.var_registers[
-                    decode_hex::<usize>(instruction_hex.substring(1, 2))
-                ] = val.1; 
+            Instruction::And(x, y) => {
+                registers[x] &= registers[y];
+                if self.quirks.vf_reset {
+                    registers[0xF] = 0;
+                }
             },
-            '8' => {
-
-                let y = self.chip8_data.var_registers[
-                    decode_hex::<usize>(instruction_hex.substring(2, 3))
-                ];
-
-                let x = &mut self.chip8_data.var_registers[
-                    decode_hex::<usize>(instruction_hex.substring(1, 2))
-                ];
-
-                match instruction_hex.chars().nth(3).unwrap() {
-                    '0' => {
-                        *x = y;
-                    },
-                    '1' => {
-                        *x |= y;
-                    },
-                    '2' => {
-                        *x &= y;
-                    },
-                    '3' => {
-                        *x ^= y;
-                    },
-                    '4' => {
-                        let (carry, val) = add_overflow(*x, y);
-                        *x = val;
-                        self.chip8_data.var_registers[0xF] = if carry { 1 } else { 0 };
-                    },
-                    '5' => {
-                        let (carry, val) = sub_overflow(*x, y);
-                        *x = val;
-                        self.chip8_data.var_registers[0xF] = if carry { 0 } else { 1 };
-                    },
-                    '6' => {
-                        let carry = least_significant_bit(*x);
-                        *x >>= 1;
-                        self.chip8_data.var_registers[0xF] = carry;
+            Instruction::Xor(x, y) => {
+                registers[x] ^= registers[y];
+                if self.quirks.vf_reset {
+                    registers[0xF] = 0;
+                }
+            },
+            Instruction::AddRegisters(x, y) => {
+                let (value, carry) = registers[x].overflowing_add(registers[y]);
+                registers[x] = value;
+                registers[0xF] = carry as u8;
+            },
+            // VF is 1 when there was no borrow
+            Instruction::Subtract(x, y) => {
+                let (value, borrow) = registers[x].overflowing_sub(registers[y]);
+                registers[x] = value;
+                registers[0xF] = !borrow as u8;
+            },
+            Instruction::SubtractReversed(x, y) => {
+                let (value, borrow) = registers[y].overflowing_sub(registers[x]);
+                registers[x] = value;
+                registers[0xF] = !borrow as u8;
+            },
+            Instruction::ShiftRight(x, y) => {
+                let value = if self.quirks.shift_vx { registers[x] } else { registers[y] };
+                registers[x] = value >> 1;
+                registers[0xF] = value & 1;
+            },
+            Instruction::ShiftLeft(x, y) => {
+                let value = if self.quirks.shift_vx { registers[x] } else { registers[y] };
+                registers[x] = value << 1;
+                registers[0xF] = value >> 7;
+            },
+            Instruction::LoadIndex(nnn) => self.chip8_data.index = nnn,
+            Instruction::JumpOffset(nnn) => {
+                let register = if self.quirks.jump_vx { (nnn >> 8) as usize } else { 0 };
+                self.chip8_data.pc = nnn + registers[register] as u16;
+                jumped = true;
+            },
+            Instruction::Random(x, nn) => {
+                let mut rng = StdRng::seed_from_u64(self.rng_state);
+                let num: u8 = rng.gen_range(0..=255);
+                self.rng_state = rng.gen();
+                registers[x] = num & nn;
+            },
+            Instruction::Draw(x, y, n) => self.draw(x, y, n),
+            Instruction::SkipPressed(x) => {
+                if self.is_pressed(self.chip8_data.var_registers[x]) {
+                    self.chip8_data.pc += 2;
+                }
+            },
+            Instruction::SkipNotPressed(x) => {
+                if !self.is_pressed(self.chip8_data.var_registers[x]) {
+                    self.chip8_data.pc += 2;
+                }
+            },
+            Instruction::Planes(planes) => self.planes = planes & 0b11,
+            Instruction::LoadAudio => {
+                let i = self.chip8_data.index as usize;
+                for offset in 0..16 {
+                    self.chip8_data.audio_pattern[offset] = self.read_memory(i + offset);
+                }
+            },
+            Instruction::LoadDelay(x) => registers[x] = self.chip8_data.delay_timer,
+            Instruction::SetDelay(x) => self.chip8_data.delay_timer = registers[x],
+            Instruction::SetSound(x) => self.chip8_data.sound_timer = registers[x],
+            Instruction::AddIndex(x) => {
+                self.chip8_data.index = self.chip8_data.index.wrapping_add(registers[x] as u16);
+            },
+            Instruction::WaitKey(x) => {
+                match self.key_wait {
+                    Some(key) if !self.is_pressed(key) => {
+                        self.chip8_data.var_registers[x] = key;
+                        self.key_wait = None;
                     },
-                    '7' => {
-                        let (carry, val) = sub_overflow(y, *x);
-                        *x = val;
-                        self.chip8_data.var_registers[0xF] = if carry { 1 } else { 0 };
+                    Some(_) => {
+                        jumped = true;
                     },
-                    'e' => {
-                        let carry = most_significant_bit(*x);
-                        *x <<= 1;
-                        self.chip8_data.var_registers[0xF] = carry;
+                    None => {
+                        self.key_wait = self.keypad.iter()
+                            .position(|pressed| *pressed)
+                            .map(|key| key as u8);
+                        jumped = true;
                     }
-                    _ => {}
                 }
-            }
-            'a' => {
-                self.chip8_data.index = decode_hex::<u16>(instruction_hex.substring(1, 4));
             },
-            'b' => {
-                let x = self.chip8_data.var_registers[
-                    decode_hex::<usize>(instruction_hex.substring(1, 2))
-                ];
-                let n: u8 = decode_hex(instruction_hex.substring(1, 4));
+            // The font starts at 0 with 5 bytes per digit, only the low nibble of VX counts
+            Instruction::Font(x) => self.chip8_data.index = (registers[x] & 0xF) as u16 * 5,
+            Instruction::Pitch(x) => self.chip8_data.pitch = registers[x],
+            Instruction::Decimal(x) => {
+                let x = registers[x];
+                let i = self.chip8_data.index as usize;
+                self.write_memory(i + 2, x % 10);
+                self.write_memory(i + 1, x / 10 % 10);
+                self.write_memory(i, x / 100);
+            },
+            Instruction::Store(x) => {
+                for i in 0..=x {
+                    self.write_memory(self.chip8_data.index as usize + i, self.chip8_data.var_registers[i]);
+                }
+                if self.quirks.memory_increment {
+                    self.chip8_data.index = self.chip8_data.index.wrapping_add(x as u16 + 1);
+                }
+            },
+            Instruction::Restore(x) => {
+                for i in 0..=x {
+                    self.chip8_data.var_registers[i] = self.read_memory(self.chip8_data.index as usize + i);
+                }
+                if self.quirks.memory_increment {
+                    self.chip8_data.index = self.chip8_data.index.wrapping_add(x as u16 + 1);
+                }
+            },
+            Instruction::Data(_) => {},
+        }
 
-                self.chip8_data.pc = (n + x) as u16;
+        if !jumped {
+            self.chip8_data.pc += 2;
+        }
+        // Jumps, skips and BNNN can go past the end of memory, PC wraps like the addresses do
+        self.chip8_data.pc &= 0xFFF;
+    }
+
+    // DXYN, `x` and `y` are the registers holding the position
+    fn draw(&mut self, x: usize, y: usize, n: usize) {
+        let width = self.display.width;
+        let height = self.display.height;
+        let x = self.chip8_data.var_registers[x] as usize % width;
+        let y = self.chip8_data.var_registers[y] as usize % height;
+        let mut i = self.chip8_data.index as usize;
+        let (rows, columns) = match n {
+            0 if self.quirks.large_sprites => {
+                let narrow = self.quirks.narrow_lores_sprites && width == display::WIDTH;
+                (16, if narrow { 8 } else { 16 })
             },
-            'c' => {
-                let mut num: u8 = rand::thread_rng().gen_range(0..=255);
-                num &= decode_hex::<u8>(instruction_hex.substring(2, 4));
-                self.chip8_data.var_registers[
-                    decode_hex::<usize>(instruction_hex.substring(1, 2))
-                ] = num;
-            }
-            'd' => {
-                let x = self.chip8_data.var_registers[
-                    decode_hex::<usize>(instruction_hex.substring(1, 2))
-                ] as usize;
-                //println!("x: {}", x);
-                let y = self.chip8_data.var_registers[
-                    decode_hex::<usize>(instruction_hex.substring(2, 3))
-                ] as usize;
-                //println!("y: {}", y);
-                let n = decode_hex::<usize>(instruction_hex.substring(3, 4));
-                //println!("n: {}", n);
-                let i = self.chip8_data.index as usize;
-                
-                'inner: for index in 0..n {
-                    if y > 31 { break 'inner; }
+            _ => (n, 8),
+        };
+        let row_bytes = columns / 8;
 
-                    let line = format!("{:08b}", self.chip8_data.memory[i+index]);
+        self.chip8_data.var_registers[0xF] = 0;
 
-                    'inner_inner: for (delta_x, pixel) in line.chars().enumerate() {
-                        if delta_x > 63 { break 'inner_inner; }
+        // With both planes selected the sprite data for plane 2 follows plane 1
+        for plane in [1, 2] {
+            if self.planes & plane == 0 {
+                continue;
+            }
 
-                        if pixel == '1' {
-                            if self.canvas.invert_pixel(x+delta_x, y + index) {
-                                self.chip8_data.var_registers[0xF] = 1;
-                            }
-                        }
-                    }
+            'rows: for row in 0..rows {
+                let mut pixel_y = y + row;
+                if pixel_y >= height {
+                    if self.quirks.clipping { break 'rows; }
+                    pixel_y %= height;
                 }
 
-                self.canvas.update();
-            },
-            'E' => {
-                let x = self.chip8_data.var_registers[
-                    decode_hex::<usize>(instruction_hex.substring(1, 2))
-                ];
-                if decode_hex::<u8>(instruction_hex.substring(2, 4)) == 0x9E {
-                    if self.events_handler.is_pressed(x) {
-                        self.chip8_data.pc += 2;
-                    }
-                } else {
-                    if !self.events_handler.is_pressed(x) {
-                        self.chip8_data.pc += 2;
+                let line = (0..row_bytes)
+                    .map(|byte| self.read_memory(i + row * row_bytes + byte) as u16)
+                    .fold(0, |line, byte| line << 8 | byte);
+                let first = 1 << (columns - 1);
+
+                'columns: for column in 0..columns {
+                    let mut pixel_x = x + column;
+                    if pixel_x >= width {
+                        if self.quirks.clipping { break 'columns; }
+                        pixel_x %= width;
                     }
-                }
-            },
-            'f' => {
-                match decode_hex::<u8>(instruction_hex.substring(2, 4)) {
-                    0x07 => {
-                        self.chip8_data.var_registers[
-                            decode_hex::<usize>(instruction_hex.substring(1, 2))
-                        ] = self.chip8_data.delay_timer;
-                    },
-                    0x15 => {
-                        self.chip8_data.delay_timer = self.chip8_data.var_registers[
-                            decode_hex::<usize>(instruction_hex.substring(1, 2))
-                        ];
-                    },
-                    0x18 => {
-                        self.chip8_data.sound_timer = self.chip8_data.var_registers[
-                            decode_hex::<usize>(instruction_hex.substring(1, 2))
-                        ];
-                    },
-                    0x1e => {
-                        self.chip8_data.index =
-                            decode_hex(instruction_hex.substring(1,2));
-                    },
-                    0x0a => {
-                        if self.events_handler.events.is_empty() {
-                            jumped = true;
-                        } else {
-                            self.chip8_data.var_registers[
-                                decode_hex::<usize>(instruction_hex.substring(1, 2))
-                            ] = self.events_handler.grab_key();
-                        }
-                    },
-                    0x29 => {
-                        self.chip8_data.index = match self.chip8_data.var_registers[
-                            decode_hex::<usize>(instruction_hex.substring(1, 2))
-                        ] {
-                            0x1 => 0,
-                            0x2 => 5,
-                            0x3 => 10,
-                            0x4 => 15,
-                            0x5 => 20,
-                            0x6 => 25,
-                            0x7 => 30,
-                            0x8 => 35,
-                            0x9 => 40,
-                            0xA => 45,
-                            0xB => 50,
-                            0xC => 55,
-                            0xD => 60,
-                            0xE => 65,
-                            0xF => 70,
-                            _ => { unreachable!() }
-                        }
 
-                    },
-                    0x33 => {
-                        let mut x = self.chip8_data.var_registers[ 
-                            decode_hex::<usize>(instruction_hex.substring(1, 2))
-                        ];
-                        self.chip8_data.memory[self.chip8_data.index as usize + 2] = x % 10;
-                        x /= 10;
-                        self.chip8_data.memory[self.chip8_data.index as usize + 1] = x % 10;
-                        x /= 10;
-                        self.chip8_data.memory[self.chip8_data.index as usize] = x % 10;
-                    },
-                    0x55 => {
-                        for i in 0..=decode_hex::<usize>(instruction_hex.substring(1, 2)) {
-                            self.chip8_data.memory[
-                                self.chip8_data.index as usize + i
-                            ] = self.chip8_data.var_registers[i];
-                        }
-                    },
-                    0x65 => {
-                        for i in 0..=decode_hex::<usize>(instruction_hex.substring(1, 2)) {
-                            self.chip8_data.var_registers[i] = 
-                            self.chip8_data.memory[
-                                self.chip8_data.index as usize + i
-                            ];
-                        }
+                    if line & (first >> column) != 0 && self.display.invert_pixel(pixel_x, pixel_y, plane) {
+                        self.chip8_data.var_registers[0xF] = 1;
                     }
-                    _ => {}
                 }
             }
-            _ => {}
+
+            i += rows * row_bytes;
         }
 
-        if !jumped {
-            self.chip8_data.pc += 2;
+        if self.quirks.display_wait {
+            self.vblank_wait = true;
         }
     }
+
 }
@@ -0,0 +1,120 @@
+use super::Emulation;
+
+// Cheats hold a memory byte at a value by writing it back at the start of every frame.
+// RAM search finds the byte to hold, e.g. a lives counter, by comparing memory between
+// snapshots: lose a life, keep the candidates that decreased, and so on.
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Cheat {
+    pub address: u16,
+    pub value: u8,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Comparison {
+    Equal,
+    Changed,
+    Increased,
+    Decreased,
+}
+
+impl Comparison {
+
+    fn matches(self, previous: u8, current: u8) -> bool {
+        match self {
+            Comparison::Equal => current == previous,
+            Comparison::Changed => current != previous,
+            Comparison::Increased => current > previous,
+            Comparison::Decreased => current < previous,
+        }
+    }
+
+}
+
+pub struct RamSearch {
+    snapshot: Vec<u8>,
+    pub candidates: Vec<u16>,
+}
+
+impl RamSearch {
+
+    // Every address is a candidate to begin with
+    pub fn new(memory: &[u8]) -> Self {
+        Self {
+            snapshot: memory.to_vec(),
+            candidates: (0..memory.len() as u16).collect(),
+        }
+    }
+
+    // Keeps the candidates whose byte compares to the last snapshot, then snapshots again
+    pub fn filter(&mut self, memory: &[u8], comparison: Comparison) {
+        self.candidates.retain(|address| {
+            let address = *address as usize;
+            comparison.matches(self.snapshot[address], memory[address])
+        });
+        self.snapshot.copy_from_slice(memory);
+    }
+
+    // The byte at `address` as of the last snapshot
+    pub fn previous(&self, address: u16) -> u8 {
+        self.snapshot[address as usize]
+    }
+
+}
+
+// One cheat per line, `address = value` in hex, `#` starts a comment
+pub fn parse(text: &str) -> Result<Vec<Cheat>, String> {
+    let mut cheats = Vec::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let line = match line.find('#') {
+            Some(comment) => &line[..comment],
+            None => line,
+        }.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let cheat = line.split_once('=')
+            .and_then(|(address, value)| {
+                let address = u16::from_str_radix(address.trim(), 16).ok().filter(|address| *address < 0x1000)?;
+                let value = u8::from_str_radix(value.trim(), 16).ok()?;
+                Some(Cheat { address, value })
+            })
+            .ok_or_else(|| format!("line {}: expected `address = value` in hex", number + 1))?;
+        cheats.push(cheat);
+    }
+
+    Ok(cheats)
+}
+
+pub fn to_text(cheats: &[Cheat]) -> String {
+    cheats.iter().map(|cheat| format!("{:03x} = {:02x}\n", cheat.address, cheat.value)).collect()
+}
+
+impl Emulation {
+
+    // Addresses past the end of memory wrap around, like every other store
+    pub fn apply_cheats(&mut self) {
+        let memory = &mut self.chip8_data.memory;
+        let len = memory.len();
+        for cheat in &self.cheats {
+            memory[cheat.address as usize % len] = cheat.value;
+        }
+    }
+
+    // Replaces any cheat already on the same address, only the low 12 bits of it count
+    pub fn set_cheat(&mut self, address: u16, value: u8) {
+        let address = address & 0xFFF;
+        match self.cheats.iter_mut().find(|cheat| cheat.address == address) {
+            Some(cheat) => cheat.value = value,
+            None => self.cheats.push(Cheat { address, value }),
+        }
+    }
+
+    pub fn remove_cheat(&mut self, address: u16) {
+        let address = address & 0xFFF;
+        self.cheats.retain(|cheat| cheat.address != address);
+    }
+
+}
@@ -1,3 +1,7 @@
+// The 5 byte hex digit sprites fill memory from address 0 up to here
+pub const FONT_END: usize = 0x50;
+
+#[derive(Clone)]
 pub struct Chip8Components {
     pub memory: [u8; 4096],
     pub pc: u16,
@@ -5,7 +9,9 @@ pub struct Chip8Components {
     pub stack: Vec<u16>,
     pub delay_timer: u8,
     pub sound_timer: u8,
-    pub var_registers: [u8; 16]
+    pub var_registers: [u8; 16],
+    pub audio_pattern: [u8; 16],
+    pub pitch: u8,
 }
 
 impl Chip8Components {
@@ -28,8 +34,17 @@ impl Chip8Components {
             stack: Vec::new(),
             delay_timer: 0,
             sound_timer: 0,
-            var_registers: [0; 16]
+            var_registers: [0; 16],
+            // a 500Hz square wave, used as the buzzer until a ROM loads its own pattern
+            audio_pattern: [0xF0; 16],
+            pitch: 64,
         }
     }
 
 }
+
+impl Default for Chip8Components {
+    fn default() -> Self {
+        Self::new()
+    }
+}
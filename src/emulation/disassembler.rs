@@ -0,0 +1,50 @@
+use super::{instruction::Instruction, quirks::Quirks};
+
+// Mnemonics in the style of Cowgod's technical reference, from the same decoded
+// `Instruction` that `execute_next_instruction` runs. BNNN depends on the quirks it runs with.
+pub fn disassemble(opcode: u16, quirks: &Quirks) -> String {
+    match Instruction::decode(opcode) {
+        Instruction::Clear => "CLS".to_string(),
+        Instruction::Return => "RET".to_string(),
+        Instruction::Lores => "LOW".to_string(),
+        Instruction::Hires => "HIGH".to_string(),
+        Instruction::Jump(nnn) => format!("JP {:03x}", nnn),
+        Instruction::Call(nnn) => format!("CALL {:03x}", nnn),
+        Instruction::SkipEqual(x, nn) => format!("SE V{:x}, {:02x}", x, nn),
+        Instruction::SkipNotEqual(x, nn) => format!("SNE V{:x}, {:02x}", x, nn),
+        Instruction::SkipEqualRegisters(x, y) => format!("SE V{:x}, V{:x}", x, y),
+        Instruction::Load(x, nn) => format!("LD V{:x}, {:02x}", x, nn),
+        Instruction::Add(x, nn) => format!("ADD V{:x}, {:02x}", x, nn),
+        Instruction::Move(x, y) => format!("LD V{:x}, V{:x}", x, y),
+        Instruction::Or(x, y) => format!("OR V{:x}, V{:x}", x, y),
+        Instruction::And(x, y) => format!("AND V{:x}, V{:x}", x, y),
+        Instruction::Xor(x, y) => format!("XOR V{:x}, V{:x}", x, y),
+        Instruction::AddRegisters(x, y) => format!("ADD V{:x}, V{:x}", x, y),
+        Instruction::Subtract(x, y) => format!("SUB V{:x}, V{:x}", x, y),
+        Instruction::ShiftRight(x, y) => format!("SHR V{:x}, V{:x}", x, y),
+        Instruction::SubtractReversed(x, y) => format!("SUBN V{:x}, V{:x}", x, y),
+        Instruction::ShiftLeft(x, y) => format!("SHL V{:x}, V{:x}", x, y),
+        Instruction::SkipNotEqualRegisters(x, y) => format!("SNE V{:x}, V{:x}", x, y),
+        Instruction::LoadIndex(nnn) => format!("LD I, {:03x}", nnn),
+        Instruction::JumpOffset(nnn) if quirks.jump_vx => format!("JP V{:x}, {:03x}", nnn >> 8, nnn),
+        Instruction::JumpOffset(nnn) => format!("JP V0, {:03x}", nnn),
+        Instruction::Random(x, nn) => format!("RND V{:x}, {:02x}", x, nn),
+        Instruction::Draw(x, y, n) => format!("DRW V{:x}, V{:x}, {:x}", x, y, n),
+        Instruction::SkipPressed(x) => format!("SKP V{:x}", x),
+        Instruction::SkipNotPressed(x) => format!("SKNP V{:x}", x),
+        Instruction::Planes(planes) => format!("PLANE {:x}", planes),
+        Instruction::LoadAudio => "AUDIO".to_string(),
+        Instruction::LoadDelay(x) => format!("LD V{:x}, DT", x),
+        Instruction::WaitKey(x) => format!("LD V{:x}, K", x),
+        Instruction::SetDelay(x) => format!("LD DT, V{:x}", x),
+        Instruction::SetSound(x) => format!("LD ST, V{:x}", x),
+        Instruction::AddIndex(x) => format!("ADD I, V{:x}", x),
+        Instruction::Font(x) => format!("LD F, V{:x}", x),
+        Instruction::Decimal(x) => format!("LD B, V{:x}", x),
+        Instruction::Pitch(x) => format!("PITCH V{:x}", x),
+        Instruction::Store(x) => format!("LD [I], V{:x}", x),
+        Instruction::Restore(x) => format!("LD V{:x}, [I]", x),
+        // Anything that isn't an instruction is most likely sprite or other data
+        Instruction::Data(opcode) => format!("DW {:04x}", opcode),
+    }
+}
@@ -0,0 +1,90 @@
+pub const WIDTH: usize = 64;
+pub const HEIGHT: usize = 32;
+// SUPER-CHIP and XO-CHIP high resolution, switched on by 00FF and off by 00FE
+pub const HIRES_WIDTH: usize = 128;
+pub const HIRES_HEIGHT: usize = 64;
+
+// Every pixel holds one bit per XO-CHIP bitplane, plain CHIP-8 only ever draws to plane 1
+pub struct Display {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<u8>,
+    // The pixels as they were at the end of the last frame, what a real screen would show
+    latched: Vec<u8>,
+}
+
+impl Display {
+
+    pub fn new() -> Self {
+        Self {
+            width: WIDTH,
+            height: HEIGHT,
+            pixels: vec![0; WIDTH * HEIGHT],
+            latched: vec![0; WIDTH * HEIGHT],
+        }
+    }
+
+    pub fn clear(&mut self, planes: u8) {
+        for pixel in self.pixels.iter_mut() {
+            *pixel &= !planes;
+        }
+    }
+
+    // The picture doesn't carry over, the screen starts blank at the new size
+    pub fn set_hires(&mut self, hires: bool) {
+        (self.width, self.height) = if hires { (HIRES_WIDTH, HIRES_HEIGHT) } else { (WIDTH, HEIGHT) };
+        self.pixels = vec![0; self.width * self.height];
+        self.latched = vec![0; self.width * self.height];
+    }
+
+    // Called once per frame at vblank
+    pub fn latch(&mut self) {
+        self.latched.copy_from_slice(&self.pixels);
+    }
+
+    pub fn latched(&self) -> &[u8] {
+        &self.latched
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [u8] {
+        &mut self.pixels
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> u8 {
+        self.pixels[y * self.width + x]
+    }
+
+    // Returns true if the pixel was turned off, which sets VF
+    pub fn invert_pixel(&mut self, x: usize, y: usize, plane: u8) -> bool {
+        let pixel = &mut self.pixels[y * self.width + x];
+        *pixel ^= plane;
+        *pixel & plane == 0
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::with_capacity((self.width + 1) * self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                text.push(match self.get(x, y) {
+                    0 => '.',
+                    1 => '#',
+                    2 => '+',
+                    _ => '@',
+                });
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+}
+
+impl Default for Display {
+    fn default() -> Self {
+        Self::new()
+    }
+}
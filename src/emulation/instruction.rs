@@ -0,0 +1,118 @@
+// One instruction taken apart, `x` and `y` are register numbers. The interpreter executes
+// these and the disassembler prints them, so the two can't disagree about what an opcode is.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Instruction {
+    Clear,
+    Return,
+    // SUPER-CHIP 00FE and 00FF
+    Lores,
+    Hires,
+    Jump(u16),
+    Call(u16),
+    SkipEqual(usize, u8),
+    SkipNotEqual(usize, u8),
+    SkipEqualRegisters(usize, usize),
+    Load(usize, u8),
+    Add(usize, u8),
+    Move(usize, usize),
+    Or(usize, usize),
+    And(usize, usize),
+    Xor(usize, usize),
+    AddRegisters(usize, usize),
+    Subtract(usize, usize),
+    ShiftRight(usize, usize),
+    SubtractReversed(usize, usize),
+    ShiftLeft(usize, usize),
+    SkipNotEqualRegisters(usize, usize),
+    LoadIndex(u16),
+    // BNNN, with the jump_vx quirk the register is the top digit of NNN instead of V0
+    JumpOffset(u16),
+    Random(usize, u8),
+    // N = 0 is a 16 row sprite with the large_sprites quirk
+    Draw(usize, usize, usize),
+    SkipPressed(usize),
+    SkipNotPressed(usize),
+    // XO-CHIP FN01, N is a bitmask of planes rather than a register
+    Planes(u8),
+    LoadAudio,
+    LoadDelay(usize),
+    WaitKey(usize),
+    SetDelay(usize),
+    SetSound(usize),
+    AddIndex(usize),
+    Font(usize),
+    Decimal(usize),
+    Pitch(usize),
+    Store(usize),
+    Restore(usize),
+    // Anything else, most likely sprite data the program never executes. 0NNN machine code
+    // calls end up here too, there is no COSMAC CPU to run them on.
+    Data(u16),
+}
+
+impl Instruction {
+
+    pub fn decode(opcode: u16) -> Self {
+        let x = (opcode >> 8 & 0xF) as usize;
+        let y = (opcode >> 4 & 0xF) as usize;
+        let n = (opcode & 0xF) as usize;
+        let nn = (opcode & 0xFF) as u8;
+        let nnn = opcode & 0xFFF;
+
+        match opcode >> 12 {
+            0x0 => match opcode {
+                0x00E0 => Instruction::Clear,
+                0x00EE => Instruction::Return,
+                0x00FE => Instruction::Lores,
+                0x00FF => Instruction::Hires,
+                _ => Instruction::Data(opcode),
+            },
+            0x1 => Instruction::Jump(nnn),
+            0x2 => Instruction::Call(nnn),
+            0x3 => Instruction::SkipEqual(x, nn),
+            0x4 => Instruction::SkipNotEqual(x, nn),
+            0x5 if n == 0 => Instruction::SkipEqualRegisters(x, y),
+            0x6 => Instruction::Load(x, nn),
+            0x7 => Instruction::Add(x, nn),
+            0x8 => match n {
+                0x0 => Instruction::Move(x, y),
+                0x1 => Instruction::Or(x, y),
+                0x2 => Instruction::And(x, y),
+                0x3 => Instruction::Xor(x, y),
+                0x4 => Instruction::AddRegisters(x, y),
+                0x5 => Instruction::Subtract(x, y),
+                0x6 => Instruction::ShiftRight(x, y),
+                0x7 => Instruction::SubtractReversed(x, y),
+                0xE => Instruction::ShiftLeft(x, y),
+                _ => Instruction::Data(opcode),
+            },
+            0x9 if n == 0 => Instruction::SkipNotEqualRegisters(x, y),
+            0xA => Instruction::LoadIndex(nnn),
+            0xB => Instruction::JumpOffset(nnn),
+            0xC => Instruction::Random(x, nn),
+            0xD => Instruction::Draw(x, y, n),
+            0xE => match nn {
+                0x9E => Instruction::SkipPressed(x),
+                0xA1 => Instruction::SkipNotPressed(x),
+                _ => Instruction::Data(opcode),
+            },
+            0xF => match nn {
+                0x01 => Instruction::Planes(x as u8),
+                0x02 if x == 0 => Instruction::LoadAudio,
+                0x07 => Instruction::LoadDelay(x),
+                0x0A => Instruction::WaitKey(x),
+                0x15 => Instruction::SetDelay(x),
+                0x18 => Instruction::SetSound(x),
+                0x1E => Instruction::AddIndex(x),
+                0x29 => Instruction::Font(x),
+                0x33 => Instruction::Decimal(x),
+                0x3A => Instruction::Pitch(x),
+                0x55 => Instruction::Store(x),
+                0x65 => Instruction::Restore(x),
+                _ => Instruction::Data(opcode),
+            },
+            _ => Instruction::Data(opcode),
+        }
+    }
+
+}
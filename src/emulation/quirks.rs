@@ -0,0 +1,106 @@
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Platform {
+    Chip8,
+    ModernChip8,
+    SuperChip,
+    XoChip,
+}
+
+impl Platform {
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "chip8" | "chip-8" | "cosmac" => Some(Platform::Chip8),
+            "modern" | "modernchip8" => Some(Platform::ModernChip8),
+            "schip" | "superchip" | "super-chip" => Some(Platform::SuperChip),
+            "xochip" | "xo-chip" => Some(Platform::XoChip),
+            _ => None,
+        }
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        match self {
+            Platform::Chip8 => Quirks {
+                vf_reset: true,
+                memory_increment: true,
+                display_wait: true,
+                clipping: true,
+                shift_vx: false,
+                jump_vx: false,
+                large_sprites: false,
+                narrow_lores_sprites: false,
+            },
+            Platform::ModernChip8 => Quirks {
+                vf_reset: false,
+                memory_increment: true,
+                display_wait: false,
+                clipping: true,
+                shift_vx: false,
+                jump_vx: false,
+                large_sprites: false,
+                narrow_lores_sprites: false,
+            },
+            Platform::SuperChip => Quirks {
+                vf_reset: false,
+                memory_increment: false,
+                display_wait: false,
+                clipping: true,
+                shift_vx: true,
+                jump_vx: true,
+                large_sprites: true,
+                narrow_lores_sprites: true,
+            },
+            Platform::XoChip => Quirks {
+                vf_reset: false,
+                memory_increment: true,
+                display_wait: false,
+                clipping: false,
+                shift_vx: false,
+                jump_vx: false,
+                large_sprites: true,
+                narrow_lores_sprites: false,
+            },
+        }
+    }
+
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Quirks {
+    // 8XY1, 8XY2 and 8XY3 reset VF to 0
+    pub vf_reset: bool,
+    // FX55 and FX65 leave I pointing past the last register
+    pub memory_increment: bool,
+    // DXYN waits for the next frame before drawing
+    pub display_wait: bool,
+    // Sprites are cut off at the screen edge instead of wrapping around
+    pub clipping: bool,
+    // 8XY6 and 8XYE shift VX in place instead of shifting VY into VX
+    pub shift_vx: bool,
+    // BNNN is read as BXNN and jumps to XNN + VX instead of NNN + V0
+    pub jump_vx: bool,
+    // DXY0 draws a 16x16 sprite, two bytes a row, instead of nothing
+    pub large_sprites: bool,
+    // In low resolution those sprites are only 8 pixels wide, as on SUPER-CHIP 1.1
+    pub narrow_lores_sprites: bool,
+}
+
+impl Quirks {
+
+    pub fn set(&mut self, name: &str, value: bool) -> Result<(), String> {
+        let quirk = match name {
+            "vf_reset" => &mut self.vf_reset,
+            "memory_increment" => &mut self.memory_increment,
+            "display_wait" => &mut self.display_wait,
+            "clipping" => &mut self.clipping,
+            "shift_vx" => &mut self.shift_vx,
+            "jump_vx" => &mut self.jump_vx,
+            "large_sprites" => &mut self.large_sprites,
+            "narrow_lores_sprites" => &mut self.narrow_lores_sprites,
+            _ => return Err(format!("unknown quirk `{}`", name)),
+        };
+        *quirk = value;
+        Ok(())
+    }
+
+}
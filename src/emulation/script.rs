@@ -0,0 +1,108 @@
+use std::collections::BTreeSet;
+
+use super::{chip::Chip8Components, Emulation};
+
+// Hooks for user scripts: bots that play the game, hitbox overlays, custom HUDs. An embedded
+// interpreter implements `Script` and forwards the calls into its own language, everything a
+// script can touch goes through `ScriptContext`.
+
+pub trait Script: Send {
+    // Once, right after the script is attached, a good place to `hook_pc`
+    fn on_attach(&mut self, _context: &mut ScriptContext) {}
+
+    // After every frame, once the timers have ticked
+    fn on_frame_end(&mut self, _context: &mut ScriptContext) {}
+
+    // Before the instruction at a hooked address executes
+    fn on_pc(&mut self, _pc: u16, _context: &mut ScriptContext) {}
+
+    // After an instruction stored `value` at `address`
+    fn on_memory_write(&mut self, _address: u16, _value: u8, _context: &mut ScriptContext) {}
+}
+
+// Text drawn over the game until the next frame starts, `x` and `y` are in display pixels
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct OverlayText {
+    pub x: usize,
+    pub y: usize,
+    pub text: String,
+}
+
+pub struct ScriptContext<'a> {
+    pub chip: &'a mut Chip8Components,
+    // Keys the script holds down, pressed on top of the player's every frame
+    pub keys: &'a mut [bool; 16],
+    pc_hooks: &'a mut BTreeSet<u16>,
+    overlay: &'a mut Vec<OverlayText>,
+}
+
+impl ScriptContext<'_> {
+
+    pub fn hook_pc(&mut self, address: u16) {
+        self.pc_hooks.insert(address);
+    }
+
+    pub fn unhook_pc(&mut self, address: u16) {
+        self.pc_hooks.remove(&address);
+    }
+
+    pub fn draw_text(&mut self, x: usize, y: usize, text: &str) {
+        self.overlay.push(OverlayText { x, y, text: text.to_string() });
+    }
+
+}
+
+// What the interpreter keeps for an attached script
+#[derive(Default)]
+pub(super) struct ScriptState {
+    pub script: Option<Box<dyn Script>>,
+    pub keys: [bool; 16],
+    pub pc_hooks: BTreeSet<u16>,
+    pub overlay: Vec<OverlayText>,
+    // Stores made by the current instruction, reported once it is done
+    pub memory_writes: Vec<(u16, u8)>,
+}
+
+impl Emulation {
+
+    // Replaces any script attached before, its hooks, keys and overlay go with it
+    pub fn attach_script(&mut self, script: Box<dyn Script>) {
+        self.scripting = ScriptState {
+            script: Some(script),
+            ..ScriptState::default()
+        };
+        self.call_script(|script, context| script.on_attach(context));
+    }
+
+    pub fn detach_script(&mut self) -> Option<Box<dyn Script>> {
+        std::mem::take(&mut self.scripting).script
+    }
+
+    pub fn overlay(&self) -> &[OverlayText] {
+        &self.scripting.overlay
+    }
+
+    pub(super) fn call_script(&mut self, call: impl FnOnce(&mut dyn Script, &mut ScriptContext)) {
+        let Some(mut script) = self.scripting.script.take() else {
+            return;
+        };
+        let mut context = ScriptContext {
+            chip: &mut self.chip8_data,
+            keys: &mut self.scripting.keys,
+            pc_hooks: &mut self.scripting.pc_hooks,
+            overlay: &mut self.scripting.overlay,
+        };
+        call(script.as_mut(), &mut context);
+        self.scripting.script = Some(script);
+    }
+
+    // Memory stores go through here so scripts can watch them, the address wraps like `read_memory`
+    pub(super) fn write_memory(&mut self, address: usize, value: u8) {
+        let address = address % self.chip8_data.memory.len();
+        self.chip8_data.memory[address] = value;
+        if self.scripting.script.is_some() {
+            self.scripting.memory_writes.push((address as u16, value));
+        }
+    }
+
+}
@@ -0,0 +1,56 @@
+// Renders the XO-CHIP 1-bit audio pattern, plain CHIP-8 ROMs get the default buzzer pattern
+pub struct PatternWave {
+    pattern: [u8; 16],
+    rate: f32,
+    position: f32,
+    playing: bool,
+}
+
+impl PatternWave {
+
+    pub fn new() -> Self {
+        Self {
+            pattern: [0; 16],
+            rate: Self::playback_rate(64),
+            position: 0.0,
+            playing: false,
+        }
+    }
+
+    // XO-CHIP plays the 128 bit pattern at 4000*2^((pitch-64)/48) bits per second
+    fn playback_rate(pitch: u8) -> f32 {
+        4000.0 * 2f32.powf((pitch as f32 - 64.0) / 48.0)
+    }
+
+    pub fn update(&mut self, pattern: &[u8; 16], pitch: u8, playing: bool) {
+        self.pattern = *pattern;
+        self.rate = Self::playback_rate(pitch);
+        if !playing {
+            self.position = 0.0;
+        }
+        self.playing = playing;
+    }
+
+    // Fills `out` with samples between -volume and volume
+    pub fn fill(&mut self, out: &mut [f32], sample_rate: u32, volume: f32) {
+        for sample in out.iter_mut() {
+            if !self.playing {
+                *sample = 0.0;
+                continue;
+            }
+
+            let bit = self.position as usize;
+            let high = self.pattern[bit / 8] & (0x80 >> (bit % 8)) != 0;
+            *sample = if high { volume } else { -volume };
+
+            self.position = (self.position + self.rate / sample_rate as f32) % 128.0;
+        }
+    }
+
+}
+
+impl Default for PatternWave {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,123 @@
+use super::{display::{self, Display}, Emulation};
+
+// Save states are a flat little endian dump of the machine, the layout only
+// ever grows at the end and the version in the magic changes when it does.
+const MAGIC: &[u8; 4] = b"C8S1";
+pub const STACK_SLOTS: usize = 16;
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.data.len() < len {
+            return Err("save state is truncated".to_string());
+        }
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+}
+
+impl Emulation {
+
+    pub fn save_state(&self) -> Vec<u8> {
+        let chip = &self.chip8_data;
+        let mut data = Vec::new();
+
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&chip.memory);
+        data.extend_from_slice(&chip.pc.to_le_bytes());
+        data.extend_from_slice(&chip.index.to_le_bytes());
+        data.push(chip.stack.len().min(STACK_SLOTS) as u8);
+        for slot in 0..STACK_SLOTS {
+            let address = chip.stack.get(slot).copied().unwrap_or(0);
+            data.extend_from_slice(&address.to_le_bytes());
+        }
+        data.push(chip.delay_timer);
+        data.push(chip.sound_timer);
+        data.extend_from_slice(&chip.var_registers);
+        data.extend_from_slice(&chip.audio_pattern);
+        data.push(chip.pitch);
+        data.push(self.planes);
+        data.push(self.key_wait.unwrap_or(0xFF));
+        data.extend_from_slice(&self.rng_state.to_le_bytes());
+        data.extend_from_slice(&(self.display.width as u16).to_le_bytes());
+        data.extend_from_slice(&(self.display.height as u16).to_le_bytes());
+        data.extend_from_slice(self.display.pixels());
+
+        data
+    }
+
+    // Leaves the emulation untouched if the state can't be read
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), String> {
+        let mut reader = Reader { data };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err("not a save state from this version".to_string());
+        }
+
+        let mut chip = self.chip8_data.clone();
+        let memory_len = chip.memory.len();
+        chip.memory.copy_from_slice(reader.take(memory_len)?);
+        chip.pc = reader.u16()? & 0xFFF;
+        chip.index = reader.u16()?;
+        let stack_len = reader.u8()? as usize;
+        if stack_len > STACK_SLOTS {
+            return Err("save state has a corrupt stack".to_string());
+        }
+        chip.stack.clear();
+        for slot in 0..STACK_SLOTS {
+            let address = reader.u16()?;
+            if slot < stack_len {
+                chip.stack.push(address & 0xFFF);
+            }
+        }
+        chip.delay_timer = reader.u8()?;
+        chip.sound_timer = reader.u8()?;
+        chip.var_registers.copy_from_slice(reader.take(16)?);
+        chip.audio_pattern.copy_from_slice(reader.take(16)?);
+        chip.pitch = reader.u8()?;
+
+        let planes = reader.u8()? & 0b11;
+        let key_wait = match reader.u8()? {
+            0xFF => None,
+            key => Some(key & 0xF),
+        };
+        let rng_state = reader.u64()?;
+
+        let width = reader.u16()? as usize;
+        let height = reader.u16()? as usize;
+        let mut display = Display::new();
+        match (width, height) {
+            (display::WIDTH, display::HEIGHT) => {},
+            (display::HIRES_WIDTH, display::HIRES_HEIGHT) => display.set_hires(true),
+            _ => return Err(format!("save state is for a {}x{} display", width, height)),
+        }
+        display.pixels_mut().copy_from_slice(reader.take(width * height)?);
+        display.latch();
+
+        self.chip8_data = chip;
+        self.display = display;
+        self.planes = planes;
+        self.key_wait = key_wait;
+        self.rng_state = rng_state;
+        self.vblank_wait = false;
+        Ok(())
+    }
+
+}
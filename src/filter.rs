@@ -0,0 +1,212 @@
+use crate::{emulation::display::Display, palette::{Palette, Rgb}};
+
+// How much of the previous frame's glow is left after each frame in phosphor mode
+const PHOSPHOR_DECAY: f32 = 0.55;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FilterMode {
+    Off,
+    // Shows a pixel if it was lit in either of the last two frames
+    Blend,
+    // Pixels fade out over a few frames instead of switching off at once
+    Phosphor,
+    // Only shows the picture latched at the end of each emulated frame, so single stepping
+    // and a breakpoint mid frame don't show sprites half erased
+    VblankOnly,
+}
+
+impl FilterMode {
+
+    pub const ALL: [FilterMode; 4] = [
+        FilterMode::Off,
+        FilterMode::Blend,
+        FilterMode::Phosphor,
+        FilterMode::VblankOnly,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "off" | "none" => Some(FilterMode::Off),
+            "blend" => Some(FilterMode::Blend),
+            "phosphor" => Some(FilterMode::Phosphor),
+            "vblank" => Some(FilterMode::VblankOnly),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FilterMode::Off => "off",
+            FilterMode::Blend => "blend",
+            FilterMode::Phosphor => "phosphor",
+            FilterMode::VblankOnly => "vblank",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|mode| mode == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+}
+
+// Turns the framebuffer into the colours that get presented
+pub struct DisplayFilter {
+    pub mode: FilterMode,
+    previous: Vec<u8>,
+    glow: Vec<[f32; 3]>,
+    output: Vec<Rgb>,
+}
+
+impl DisplayFilter {
+
+    pub fn new(mode: FilterMode) -> Self {
+        Self {
+            mode,
+            previous: Vec::new(),
+            glow: Vec::new(),
+            output: Vec::new(),
+        }
+    }
+
+    // Should be called exactly once per frame, the blend and phosphor modes keep history
+    pub fn apply(&mut self, display: &Display, palette: &Palette) -> &[Rgb] {
+        let size = display.width * display.height;
+        if self.output.len() != size {
+            self.previous = vec![0; size];
+            self.glow = vec![to_glow(palette.colors[0]); size];
+            self.output = vec![palette.colors[0]; size];
+        }
+
+        let pixels = match self.mode {
+            FilterMode::VblankOnly => display.latched(),
+            _ => display.pixels(),
+        };
+
+        for y in 0..display.height {
+            for x in 0..display.width {
+                let index = y * display.width + x;
+                let pixel = pixels[index] & 0b11;
+
+                self.output[index] = match self.mode {
+                    FilterMode::Off | FilterMode::VblankOnly => palette.colors[pixel as usize],
+                    FilterMode::Blend => palette.colors[(pixel | self.previous[index]) as usize],
+                    FilterMode::Phosphor => {
+                        let target = to_glow(palette.colors[pixel as usize]);
+                        let glow = &mut self.glow[index];
+                        if pixel != 0 {
+                            *glow = target;
+                        } else {
+                            for channel in 0..3 {
+                                glow[channel] = target[channel] + (glow[channel] - target[channel]) * PHOSPHOR_DECAY;
+                            }
+                        }
+                        (glow[0] as u8, glow[1] as u8, glow[2] as u8)
+                    }
+                };
+
+                self.previous[index] = pixel;
+                if self.mode != FilterMode::Phosphor {
+                    self.glow[index] = to_glow(self.output[index]);
+                }
+            }
+        }
+
+        &self.output
+    }
+
+}
+
+fn to_glow((r, g, b): Rgb) -> [f32; 3] {
+    [r as f32, g as f32, b as f32]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::emulation::{quirks::Platform, Emulation};
+
+    use super::*;
+
+    const BLACK: Rgb = (0, 0, 0);
+    const WHITE: Rgb = (200, 200, 200);
+
+    fn palette() -> Palette {
+        Palette {
+            name: "test".to_string(),
+            colors: [BLACK, WHITE, WHITE, WHITE],
+        }
+    }
+
+    // One frame through the filter with only the top left pixel lit or not
+    fn frame(filter: &mut DisplayFilter, display: &mut Display, lit: bool) -> Rgb {
+        display.pixels_mut()[0] = lit as u8;
+        filter.apply(display, &palette())[0]
+    }
+
+    #[test]
+    fn blend_keeps_a_pixel_lit_for_one_more_frame() {
+        let mut filter = DisplayFilter::new(FilterMode::Blend);
+        let mut display = Display::new();
+
+        assert_eq!(frame(&mut filter, &mut display, true), WHITE);
+        assert_eq!(frame(&mut filter, &mut display, false), WHITE);
+        assert_eq!(frame(&mut filter, &mut display, false), BLACK);
+        assert_eq!(frame(&mut filter, &mut display, true), WHITE);
+    }
+
+    #[test]
+    fn phosphor_fades_out() {
+        let mut filter = DisplayFilter::new(FilterMode::Phosphor);
+        let mut display = Display::new();
+
+        assert_eq!(frame(&mut filter, &mut display, true), WHITE);
+        assert_eq!(frame(&mut filter, &mut display, false), (110, 110, 110));
+        assert_eq!(frame(&mut filter, &mut display, false), (60, 60, 60));
+        for _ in 0..20 {
+            frame(&mut filter, &mut display, false);
+        }
+        assert_eq!(frame(&mut filter, &mut display, false), BLACK);
+        assert_eq!(frame(&mut filter, &mut display, true), WHITE);
+    }
+
+    #[test]
+    fn off_shows_every_frame() {
+        let mut filter = DisplayFilter::new(FilterMode::Off);
+        let mut display = Display::new();
+
+        assert_eq!(frame(&mut filter, &mut display, true), WHITE);
+        assert_eq!(frame(&mut filter, &mut display, false), BLACK);
+    }
+
+    #[test]
+    fn vblank_only_shows_the_latched_frame() {
+        let mut filter = DisplayFilter::new(FilterMode::VblankOnly);
+        let mut display = Display::new();
+
+        assert_eq!(frame(&mut filter, &mut display, true), BLACK);
+        display.latch();
+        assert_eq!(frame(&mut filter, &mut display, false), WHITE);
+        display.latch();
+        assert_eq!(frame(&mut filter, &mut display, false), BLACK);
+    }
+
+    #[test]
+    fn vblank_only_updates_when_every_draw_collides() {
+        // Lights pixel 0, then moves it right one pixel a frame by drawing two pixels over it
+        let rom = [
+            0xA2, 0x10, 0xD0, 0x11, 0xA2, 0x11, 0xD0, 0x11, 0x70, 0x01, 0x12, 0x06,
+            0x00, 0x00, 0x00, 0x00, 0x80, 0xC0,
+        ];
+        let mut emulation = Emulation::new(Platform::Chip8, 0);
+        emulation.load_rom(&rom).unwrap();
+        let mut filter = DisplayFilter::new(FilterMode::VblankOnly);
+        emulation.run_frame(1000);
+
+        for x in 1..10 {
+            emulation.run_frame(1000);
+            assert_eq!(emulation.chip8_data.var_registers[0xF], 1);
+            let output = filter.apply(&emulation.display, &palette());
+            assert_eq!((output[x - 1], output[x]), (BLACK, WHITE), "frame {}", x);
+        }
+    }
+}
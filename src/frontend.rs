@@ -0,0 +1,112 @@
+use std::path::PathBuf;
+
+use crate::{emulation::{cheats::Comparison, Emulation}, palette::Rgb};
+
+// Fullscreen, IntegerScale, Browser, Osd, Step and Debugger only exist in the SDL frontend, as does binding hotkeys by name
+#[cfg_attr(not(feature = "sdl"), allow(dead_code))]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Hotkey {
+    Quit,
+    Pause,
+    Reset,
+    FrameAdvance,
+    // Only has an effect while held, see `Frontend::held`
+    FastForward,
+    SlowMotion,
+    Browser,
+    Osd,
+    // Runs one instruction while paused
+    Step,
+    Debugger,
+    Fullscreen,
+    IntegerScale,
+    CyclePalette,
+    CycleFilter,
+}
+
+impl Hotkey {
+
+    #[cfg_attr(not(feature = "sdl"), allow(dead_code))]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "quit" => Some(Hotkey::Quit),
+            "pause" => Some(Hotkey::Pause),
+            "reset" => Some(Hotkey::Reset),
+            "frame_advance" => Some(Hotkey::FrameAdvance),
+            "fast_forward" => Some(Hotkey::FastForward),
+            "slow_motion" => Some(Hotkey::SlowMotion),
+            "browser" => Some(Hotkey::Browser),
+            "osd" => Some(Hotkey::Osd),
+            "step" => Some(Hotkey::Step),
+            "debugger" => Some(Hotkey::Debugger),
+            "fullscreen" => Some(Hotkey::Fullscreen),
+            "integer_scale" => Some(Hotkey::IntegerScale),
+            "cycle_palette" => Some(Hotkey::CyclePalette),
+            "cycle_filter" => Some(Hotkey::CycleFilter),
+            _ => None,
+        }
+    }
+
+}
+
+// Changes a debugger asks the main loop to make to the emulation
+#[cfg_attr(not(feature = "sdl"), allow(dead_code))]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DebugCommand {
+    ToggleBreakpoint(u16),
+    WriteMemory(u16, u8),
+    // Narrows the RAM search, None starts a new one
+    Search(Option<Comparison>),
+    // Holds the byte at an address at its current value
+    Freeze(u16),
+    SetCheat(u16, u8),
+    RemoveCheat(u16),
+}
+
+// What the main loop needs from a window or terminal to run a ROM
+pub trait Frontend {
+    // Updates the held keypad keys and returns the hotkeys pressed since the last call.
+    // Hotkeys that only concern the frontend itself, like fullscreen, are handled inside.
+    fn poll_input(&mut self, keypad: &mut [bool; 16]) -> Vec<Hotkey>;
+
+    // Whether the key bound to `hotkey` is down, as of the last `poll_input`
+    fn held(&self, hotkey: Hotkey) -> bool;
+
+    // `frame` is the filtered display in palette colours, one entry per pixel
+    fn present(&mut self, emulation: &Emulation, frame: &[Rgb]);
+
+    // Whether `present` waits for the monitor's vblank, the main loop only sleeps if not
+    fn vsync(&self) -> bool {
+        false
+    }
+
+    fn play_sound(&mut self, _pattern: &[u8; 16], _pitch: u8, _playing: bool) {}
+
+    // A ROM file dropped onto the window or picked in the ROM browser since the last call
+    fn requested_rom(&mut self) -> Option<PathBuf> {
+        None
+    }
+
+    // For frontends that have a debugger, it is opened once before the first frame with --debug
+    fn open_debugger(&mut self) {}
+
+    // Breakpoints and memory edits made in a debugger since the last call
+    fn debug_commands(&mut self) -> Vec<DebugCommand> {
+        Vec::new()
+    }
+
+    // The game is not run while a menu covers it
+    fn menu_open(&self) -> bool {
+        false
+    }
+
+    // Short notices such as "Paused", for frontends with an on-screen display
+    fn show_message(&mut self, _message: &str) {}
+
+    // How many emulated frames run per presented one, 0 while paused
+    fn set_speed(&mut self, _speed: f32) {}
+
+    fn show_error(&mut self, message: &str) {
+        eprintln!("{}", message);
+    }
+}
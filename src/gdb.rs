@@ -0,0 +1,348 @@
+use std::{
+    io::{ErrorKind, Read, Write},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::emulation::{Emulation, STACK_SLOTS};
+
+// A GDB remote serial protocol stub, so CHIP-8 programs can be debugged from GDB or anything
+// else that speaks RSP. It is polled from the main loop once per frame and drives the same
+// pause flag and breakpoints as the built-in debugger.
+//
+// Registers are V0-VF, I, PC, SP (the stack depth), DT and ST, in that order, little endian.
+
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.chip8.cpu">
+    <reg name="v0" bitsize="8"/><reg name="v1" bitsize="8"/><reg name="v2" bitsize="8"/><reg name="v3" bitsize="8"/>
+    <reg name="v4" bitsize="8"/><reg name="v5" bitsize="8"/><reg name="v6" bitsize="8"/><reg name="v7" bitsize="8"/>
+    <reg name="v8" bitsize="8"/><reg name="v9" bitsize="8"/><reg name="va" bitsize="8"/><reg name="vb" bitsize="8"/>
+    <reg name="vc" bitsize="8"/><reg name="vd" bitsize="8"/><reg name="ve" bitsize="8"/><reg name="vf" bitsize="8"/>
+    <reg name="i" bitsize="16" type="data_ptr"/>
+    <reg name="pc" bitsize="16" type="code_ptr"/>
+    <reg name="sp" bitsize="8"/>
+    <reg name="dt" bitsize="8"/>
+    <reg name="st" bitsize="8"/>
+  </feature>
+</target>
+"#;
+
+const REGISTER_SIZES: [usize; 21] = [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 1, 1, 1];
+const MEMORY_SIZE: usize = 0x1000;
+
+const SIGINT: u8 = 2;
+const SIGTRAP: u8 = 5;
+
+pub struct GdbStub {
+    // None once the debugger has detached
+    client: Option<TcpStream>,
+    buffer: Vec<u8>,
+    // GDB sent a continue and waits for the stop reply
+    running: bool,
+    // Packets are acknowledged until GDB turns that off
+    ack: bool,
+}
+
+impl GdbStub {
+
+    // Blocks until a debugger connects, like gdbserver the program stays halted until then
+    pub fn listen(port: u16) -> Result<Self, String> {
+        let listener = TcpListener::bind(("127.0.0.1", port)).map_err(|err| format!("port {}: {}", port, err))?;
+        let (stream, _) = listener.accept().map_err(|err| err.to_string())?;
+        stream.set_nodelay(true).map_err(|err| err.to_string())?;
+        stream.set_nonblocking(true).map_err(|err| err.to_string())?;
+
+        Ok(Self {
+            client: Some(stream),
+            buffer: Vec::new(),
+            running: false,
+            ack: true,
+        })
+    }
+
+    pub fn is_attached(&self) -> bool {
+        self.client.is_some()
+    }
+
+    // Handles everything GDB sent since the last call. `paused` is the main loop's pause flag,
+    // it is set while GDB has the program stopped and setting it from elsewhere (a breakpoint
+    // or the pause hotkey) is reported to GDB as a stop.
+    pub fn poll(&mut self, emulation: &mut Emulation, paused: &mut bool) {
+        if self.client.is_none() {
+            return;
+        }
+        if self.running && *paused {
+            self.running = false;
+            self.send_packet(&format!("S{:02x}", SIGTRAP));
+        }
+
+        let mut chunk = [0; 1024];
+        loop {
+            let Some(client) = self.client.as_mut() else {
+                return;
+            };
+            match client.read(&mut chunk) {
+                Ok(0) => {
+                    self.detach(paused);
+                    return;
+                },
+                Ok(read) => self.buffer.extend_from_slice(&chunk[..read]),
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {},
+                Err(_) => {
+                    self.detach(paused);
+                    return;
+                },
+            }
+        }
+
+        while let Some(packet) = self.next_packet() {
+            match packet {
+                Packet::Interrupt => {
+                    *paused = true;
+                    if self.running {
+                        self.running = false;
+                        self.send_packet(&format!("S{:02x}", SIGINT));
+                    }
+                },
+                Packet::Corrupt => self.send_raw(b"-"),
+                Packet::Command(command) => {
+                    if self.ack {
+                        self.send_raw(b"+");
+                    }
+                    if let Some(reply) = self.handle(&command, emulation, paused) {
+                        self.send_packet(&reply);
+                    }
+                },
+            }
+            if self.client.is_none() {
+                return;
+            }
+        }
+    }
+
+    // Takes the next complete packet off the buffer, acks and resends are ignored
+    fn next_packet(&mut self) -> Option<Packet> {
+        loop {
+            match *self.buffer.first()? {
+                0x03 => {
+                    self.buffer.remove(0);
+                    return Some(Packet::Interrupt);
+                },
+                b'$' => break,
+                _ => {
+                    self.buffer.remove(0);
+                },
+            }
+        }
+
+        let end = self.buffer.iter().position(|byte| *byte == b'#')?;
+        if self.buffer.len() < end + 3 {
+            return None;
+        }
+        let packet: Vec<u8> = self.buffer.drain(..end + 3).collect();
+        let data = &packet[1..end];
+        let checksum = std::str::from_utf8(&packet[end + 1..]).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        if checksum != Some(checksum_of(data)) {
+            return Some(Packet::Corrupt);
+        }
+        Some(Packet::Command(String::from_utf8_lossy(data).into_owned()))
+    }
+
+    // Returns the reply, None when it comes later (continue) or not at all
+    fn handle(&mut self, command: &str, emulation: &mut Emulation, paused: &mut bool) -> Option<String> {
+        let (kind, args) = command.split_at(command.chars().next().map_or(0, char::len_utf8));
+
+        let reply = match kind {
+            "?" => format!("S{:02x}", SIGTRAP),
+            "g" => hex::encode(read_registers(emulation)),
+            "G" => {
+                let Ok(bytes) = hex::decode(args) else {
+                    return Some("E01".to_string());
+                };
+                let mut offset = 0;
+                for (register, size) in REGISTER_SIZES.iter().enumerate() {
+                    if let Some(value) = bytes.get(offset..offset + size) {
+                        write_register(emulation, register, value);
+                    }
+                    offset += size;
+                }
+                "OK".to_string()
+            },
+            "p" => match usize::from_str_radix(args, 16).ok().filter(|register| *register < REGISTER_SIZES.len()) {
+                Some(register) => {
+                    let offset: usize = REGISTER_SIZES[..register].iter().sum();
+                    hex::encode(&read_registers(emulation)[offset..offset + REGISTER_SIZES[register]])
+                },
+                None => "E01".to_string(),
+            },
+            "P" => {
+                let parsed = args.split_once('=').and_then(|(register, value)| {
+                    let register = usize::from_str_radix(register, 16).ok().filter(|register| *register < REGISTER_SIZES.len())?;
+                    Some((register, hex::decode(value).ok()?))
+                });
+                match parsed {
+                    Some((register, value)) if value.len() == REGISTER_SIZES[register] => {
+                        write_register(emulation, register, &value);
+                        "OK".to_string()
+                    },
+                    _ => "E01".to_string(),
+                }
+            },
+            "m" => match parse_range(args) {
+                Some((address, length)) => hex::encode(&emulation.chip8_data.memory[address..address + length]),
+                None => "E01".to_string(),
+            },
+            "M" => {
+                let parsed = args.split_once(':').and_then(|(range, data)| {
+                    let (address, length) = parse_range(range)?;
+                    Some((address, hex::decode(data).ok().filter(|bytes| bytes.len() == length)?))
+                });
+                match parsed {
+                    Some((address, bytes)) => {
+                        emulation.chip8_data.memory[address..address + bytes.len()].copy_from_slice(&bytes);
+                        "OK".to_string()
+                    },
+                    None => "E01".to_string(),
+                }
+            },
+            // Software and hardware breakpoints are the same thing here
+            "Z" | "z" => {
+                let mut fields = args.split(',');
+                let breakpoint_kind = fields.next();
+                let address = fields.next().and_then(|address| u16::from_str_radix(address, 16).ok());
+                match (breakpoint_kind, address) {
+                    (Some("0" | "1"), Some(address)) => {
+                        if kind == "Z" {
+                            emulation.breakpoints.insert(address);
+                        } else {
+                            emulation.breakpoints.remove(&address);
+                        }
+                        "OK".to_string()
+                    },
+                    _ => String::new(),
+                }
+            },
+            "s" | "c" => {
+                if let Ok(address) = u16::from_str_radix(args, 16) {
+                    emulation.chip8_data.pc = address & 0xFFF;
+                }
+                if kind == "s" {
+                    emulation.step();
+                    format!("S{:02x}", SIGTRAP)
+                } else {
+                    *paused = false;
+                    self.running = true;
+                    return None;
+                }
+            },
+            "H" | "T" => "OK".to_string(),
+            "D" => {
+                self.send_packet("OK");
+                self.detach(paused);
+                return None;
+            },
+            "k" => {
+                self.detach(paused);
+                return None;
+            },
+            _ => self.query(command)?,
+        };
+        Some(reply)
+    }
+
+    // The general queries GDB sends while connecting
+    fn query(&mut self, command: &str) -> Option<String> {
+        if command.starts_with("qSupported") {
+            return Some("PacketSize=1000;qXfer:features:read+;QStartNoAckMode+".to_string());
+        }
+        if command == "QStartNoAckMode" {
+            self.send_packet("OK");
+            self.ack = false;
+            return None;
+        }
+        if let Some(range) = command.strip_prefix("qXfer:features:read:target.xml:") {
+            let (offset, length) = range.split_once(',')?;
+            let offset = usize::from_str_radix(offset, 16).ok()?.min(TARGET_XML.len());
+            let length = usize::from_str_radix(length, 16).ok()?;
+            let end = (offset + length).min(TARGET_XML.len());
+            let more = if end < TARGET_XML.len() { 'm' } else { 'l' };
+            return Some(format!("{}{}", more, &TARGET_XML[offset..end]));
+        }
+        let reply = match command {
+            "qAttached" => "1",
+            "qC" => "QC1",
+            "qfThreadInfo" => "m1",
+            "qsThreadInfo" => "l",
+            // An empty reply means not supported
+            _ => "",
+        };
+        Some(reply.to_string())
+    }
+
+    // The program carries on by itself once the debugger is gone
+    fn detach(&mut self, paused: &mut bool) {
+        self.client = None;
+        self.running = false;
+        *paused = false;
+    }
+
+    fn send_packet(&mut self, data: &str) {
+        let packet = format!("${}#{:02x}", data, checksum_of(data.as_bytes()));
+        self.send_raw(packet.as_bytes());
+    }
+
+    fn send_raw(&mut self, data: &[u8]) {
+        let Some(client) = self.client.as_mut() else {
+            return;
+        };
+        // Replies are small enough for the socket buffer, a failed write means GDB is gone
+        if client.write_all(data).is_err() {
+            self.client = None;
+            self.running = false;
+        }
+    }
+
+}
+
+enum Packet {
+    Command(String),
+    Corrupt,
+    Interrupt,
+}
+
+fn read_registers(emulation: &Emulation) -> Vec<u8> {
+    let chip = &emulation.chip8_data;
+    let mut registers = chip.var_registers.to_vec();
+    registers.extend_from_slice(&chip.index.to_le_bytes());
+    registers.extend_from_slice(&chip.pc.to_le_bytes());
+    registers.extend_from_slice(&[chip.stack.len() as u8, chip.delay_timer, chip.sound_timer]);
+    registers
+}
+
+// `value` is the register's size, see REGISTER_SIZES
+fn write_register(emulation: &mut Emulation, register: usize, value: &[u8]) {
+    let chip = &mut emulation.chip8_data;
+    match register {
+        0..=15 => chip.var_registers[register] = value[0],
+        16 => chip.index = u16::from_le_bytes([value[0], value[1]]) & 0xFFF,
+        17 => chip.pc = u16::from_le_bytes([value[0], value[1]]) & 0xFFF,
+        // Growing the stack pushes return addresses of 0
+        18 => chip.stack.resize((value[0] as usize).min(STACK_SLOTS), 0),
+        19 => chip.delay_timer = value[0],
+        _ => chip.sound_timer = value[0],
+    }
+}
+
+// `address,length` in hex, all of it has to lie within memory
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    let (address, length) = range.split_once(',')?;
+    let address = usize::from_str_radix(address, 16).ok()?;
+    let length = usize::from_str_radix(length, 16).ok()?;
+    (address.checked_add(length)? <= MEMORY_SIZE).then_some((address, length))
+}
+
+fn checksum_of(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+}
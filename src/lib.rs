@@ -0,0 +1,2 @@
+pub mod emulation;
+pub mod libretro;
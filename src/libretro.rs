@@ -0,0 +1,382 @@
+use std::{
+    ffi::{c_char, c_void, CStr},
+    sync::Mutex,
+};
+
+use crate::emulation::{cheats, display, quirks::Platform, sound::PatternWave, Emulation};
+
+// A libretro core (https://docs.libretro.com/development/cores/developing-cores/)
+// so RetroArch and other libretro frontends can host the interpreter.
+
+const RETRO_API_VERSION: u32 = 1;
+const RETRO_ENVIRONMENT_SET_PIXEL_FORMAT: u32 = 10;
+const RETRO_PIXEL_FORMAT_XRGB8888: u32 = 1;
+const RETRO_DEVICE_JOYPAD: u32 = 1;
+const RETRO_DEVICE_KEYBOARD: u32 = 3;
+const RETRO_MEMORY_SYSTEM_RAM: u32 = 2;
+const RETRO_REGION_NTSC: u32 = 0;
+
+const INSTRUCTIONS_PER_FRAME: u32 = 10;
+const SAMPLE_RATE: u32 = 44100;
+const SAMPLES_PER_FRAME: usize = SAMPLE_RATE as usize / 60;
+const VOLUME: f32 = 0.15;
+
+// XRGB8888 colours for each bitplane combination
+const COLORS: [u32; 4] = [0x000000, 0xFFFFFF, 0xAAAAAA, 0x555555];
+
+// Joypad button ids (B, Y, Select, Start, Up, Down, Left, Right, A, X, L, R, ...) to keypad keys
+const JOYPAD: [(u32, u8); 12] = [
+    (0, 0x4), (1, 0xB), (2, 0xE), (3, 0xF), (4, 0x5), (5, 0x8),
+    (6, 0x7), (7, 0x9), (8, 0x6), (9, 0xA), (10, 0x1), (11, 0xC),
+];
+
+// RETROK_* codes for the usual 1234/QWER/ASDF/ZXCV layout
+const KEYBOARD: [(u32, u8); 16] = [
+    (b'1' as u32, 0x1), (b'2' as u32, 0x2), (b'3' as u32, 0x3), (b'4' as u32, 0xC),
+    (b'q' as u32, 0x4), (b'w' as u32, 0x5), (b'e' as u32, 0x6), (b'r' as u32, 0xD),
+    (b'a' as u32, 0x7), (b's' as u32, 0x8), (b'd' as u32, 0x9), (b'f' as u32, 0xE),
+    (b'z' as u32, 0xA), (b'x' as u32, 0x0), (b'c' as u32, 0xB), (b'v' as u32, 0xF),
+];
+
+pub type EnvironmentFn = unsafe extern "C" fn(cmd: u32, data: *mut c_void) -> bool;
+pub type VideoRefreshFn = unsafe extern "C" fn(data: *const c_void, width: u32, height: u32, pitch: usize);
+pub type AudioSampleFn = unsafe extern "C" fn(left: i16, right: i16);
+pub type AudioSampleBatchFn = unsafe extern "C" fn(data: *const i16, frames: usize) -> usize;
+pub type InputPollFn = unsafe extern "C" fn();
+pub type InputStateFn = unsafe extern "C" fn(port: u32, device: u32, index: u32, id: u32) -> i16;
+
+#[repr(C)]
+pub struct RetroSystemInfo {
+    pub library_name: *const c_char,
+    pub library_version: *const c_char,
+    pub valid_extensions: *const c_char,
+    pub need_fullpath: bool,
+    pub block_extract: bool,
+}
+
+#[repr(C)]
+pub struct RetroGameGeometry {
+    pub base_width: u32,
+    pub base_height: u32,
+    pub max_width: u32,
+    pub max_height: u32,
+    pub aspect_ratio: f32,
+}
+
+#[repr(C)]
+pub struct RetroSystemTiming {
+    pub fps: f64,
+    pub sample_rate: f64,
+}
+
+#[repr(C)]
+pub struct RetroSystemAvInfo {
+    pub geometry: RetroGameGeometry,
+    pub timing: RetroSystemTiming,
+}
+
+#[repr(C)]
+pub struct RetroGameInfo {
+    pub path: *const c_char,
+    pub data: *const c_void,
+    pub size: usize,
+    pub meta: *const c_char,
+}
+
+struct Callbacks {
+    environment: Option<EnvironmentFn>,
+    video_refresh: Option<VideoRefreshFn>,
+    audio_sample_batch: Option<AudioSampleBatchFn>,
+    input_poll: Option<InputPollFn>,
+    input_state: Option<InputStateFn>,
+}
+
+struct Core {
+    emulation: Emulation,
+    wave: PatternWave,
+    video: Vec<u32>,
+    audio: Vec<f32>,
+}
+
+static CALLBACKS: Mutex<Callbacks> = Mutex::new(Callbacks {
+    environment: None,
+    video_refresh: None,
+    audio_sample_batch: None,
+    input_poll: None,
+    input_state: None,
+});
+static CORE: Mutex<Option<Core>> = Mutex::new(None);
+
+impl Core {
+
+    fn new(rom: &[u8]) -> Result<Self, String> {
+        let mut emulation = Emulation::new(Platform::Chip8, rand::random());
+        emulation.load_rom(rom)?;
+
+        Ok(Self {
+            emulation,
+            wave: PatternWave::new(),
+            video: Vec::new(),
+            audio: vec![0.0; SAMPLES_PER_FRAME],
+        })
+    }
+
+    fn read_input(&mut self, input_state: InputStateFn) {
+        let mut keypad = [false; 16];
+        for (id, key) in JOYPAD {
+            if unsafe { input_state(0, RETRO_DEVICE_JOYPAD, 0, id) } != 0 {
+                keypad[key as usize] = true;
+            }
+        }
+        for (code, key) in KEYBOARD {
+            if unsafe { input_state(0, RETRO_DEVICE_KEYBOARD, 0, code) } != 0 {
+                keypad[key as usize] = true;
+            }
+        }
+        self.emulation.keypad = keypad;
+    }
+
+    fn render(&mut self) {
+        let display = &self.emulation.display;
+        self.video.clear();
+        self.video.extend(display.pixels().iter().map(|pixel| COLORS[(pixel & 0b11) as usize]));
+    }
+
+}
+
+#[no_mangle]
+pub extern "C" fn retro_api_version() -> u32 {
+    RETRO_API_VERSION
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_environment(callback: EnvironmentFn) {
+    CALLBACKS.lock().unwrap().environment = Some(callback);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_video_refresh(callback: VideoRefreshFn) {
+    CALLBACKS.lock().unwrap().video_refresh = Some(callback);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_audio_sample(_callback: AudioSampleFn) {}
+
+#[no_mangle]
+pub extern "C" fn retro_set_audio_sample_batch(callback: AudioSampleBatchFn) {
+    CALLBACKS.lock().unwrap().audio_sample_batch = Some(callback);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_input_poll(callback: InputPollFn) {
+    CALLBACKS.lock().unwrap().input_poll = Some(callback);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_input_state(callback: InputStateFn) {
+    CALLBACKS.lock().unwrap().input_state = Some(callback);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_init() {}
+
+#[no_mangle]
+pub extern "C" fn retro_deinit() {
+    *CORE.lock().unwrap() = None;
+}
+
+/// # Safety
+/// `info` must point to a writable `retro_system_info`.
+#[no_mangle]
+pub unsafe extern "C" fn retro_get_system_info(info: *mut RetroSystemInfo) {
+    *info = RetroSystemInfo {
+        library_name: c"CHIP-8".as_ptr(),
+        library_version: c"0.1.0".as_ptr(),
+        valid_extensions: c"ch8|c8|rom".as_ptr(),
+        need_fullpath: true,
+        block_extract: false,
+    };
+}
+
+/// # Safety
+/// `info` must point to a writable `retro_system_av_info`.
+#[no_mangle]
+pub unsafe extern "C" fn retro_get_system_av_info(info: *mut RetroSystemAvInfo) {
+    *info = RetroSystemAvInfo {
+        geometry: RetroGameGeometry {
+            base_width: display::WIDTH as u32,
+            base_height: display::HEIGHT as u32,
+            max_width: display::HIRES_WIDTH as u32,
+            max_height: display::HIRES_HEIGHT as u32,
+            aspect_ratio: 2.0,
+        },
+        timing: RetroSystemTiming {
+            fps: 60.0,
+            sample_rate: SAMPLE_RATE as f64,
+        },
+    };
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_controller_port_device(_port: u32, _device: u32) {}
+
+#[no_mangle]
+pub extern "C" fn retro_reset() {
+    if let Some(core) = CORE.lock().unwrap().as_mut() {
+        core.emulation.reset();
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_run() {
+    let callbacks = CALLBACKS.lock().unwrap();
+    let mut core = CORE.lock().unwrap();
+    let Some(core) = core.as_mut() else {
+        return;
+    };
+
+    if let (Some(input_poll), Some(input_state)) = (callbacks.input_poll, callbacks.input_state) {
+        unsafe { input_poll() };
+        core.read_input(input_state);
+    }
+
+    core.emulation.run_frame(INSTRUCTIONS_PER_FRAME);
+
+    if let Some(video_refresh) = callbacks.video_refresh {
+        core.render();
+        let (width, height) = (core.emulation.display.width, core.emulation.display.height);
+        unsafe { video_refresh(core.video.as_ptr() as *const c_void, width as u32, height as u32, width * 4) };
+    }
+
+    if let Some(audio_sample_batch) = callbacks.audio_sample_batch {
+        let chip = &core.emulation.chip8_data;
+        core.wave.update(&chip.audio_pattern, chip.pitch, core.emulation.sound_playing());
+        core.wave.fill(&mut core.audio, SAMPLE_RATE, VOLUME);
+
+        let stereo: Vec<i16> = core.audio.iter()
+            .flat_map(|sample| {
+                let sample = (sample * i16::MAX as f32) as i16;
+                [sample, sample]
+            })
+            .collect();
+        unsafe { audio_sample_batch(stereo.as_ptr(), SAMPLES_PER_FRAME) };
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_serialize_size() -> usize {
+    CORE.lock().unwrap().as_ref().map_or(0, |core| core.emulation.save_state().len())
+}
+
+/// # Safety
+/// `data` must point to at least `size` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn retro_serialize(data: *mut c_void, size: usize) -> bool {
+    let core = CORE.lock().unwrap();
+    let Some(core) = core.as_ref() else {
+        return false;
+    };
+
+    let state = core.emulation.save_state();
+    if state.len() > size {
+        return false;
+    }
+    std::ptr::copy_nonoverlapping(state.as_ptr(), data as *mut u8, state.len());
+    true
+}
+
+/// # Safety
+/// `data` must point to at least `size` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn retro_unserialize(data: *const c_void, size: usize) -> bool {
+    let mut core = CORE.lock().unwrap();
+    let Some(core) = core.as_mut() else {
+        return false;
+    };
+
+    let state = std::slice::from_raw_parts(data as *const u8, size);
+    core.emulation.load_state(state).is_ok()
+}
+
+#[no_mangle]
+pub extern "C" fn retro_cheat_reset() {
+    if let Some(core) = CORE.lock().unwrap().as_mut() {
+        core.emulation.cheats.clear();
+    }
+}
+
+// Codes use the cheat file syntax, several can be joined with `+`. Invalid ones are ignored.
+/// # Safety
+/// `code` must be null or a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn retro_cheat_set(_index: u32, enabled: bool, code: *const c_char) {
+    if !enabled || code.is_null() {
+        return;
+    }
+    let mut core = CORE.lock().unwrap();
+    let Some(core) = core.as_mut() else {
+        return;
+    };
+
+    let code = CStr::from_ptr(code).to_string_lossy().replace('+', "\n");
+    if let Ok(cheats) = cheats::parse(&code) {
+        core.emulation.cheats.extend(cheats);
+    }
+}
+
+/// # Safety
+/// `game` must be null or point to a valid `retro_game_info` with a path.
+#[no_mangle]
+pub unsafe extern "C" fn retro_load_game(game: *const RetroGameInfo) -> bool {
+    if game.is_null() || (*game).path.is_null() {
+        return false;
+    }
+    let Ok(path) = CStr::from_ptr((*game).path).to_str() else {
+        return false;
+    };
+    let Ok(core) = std::fs::read(path).map_err(|err| err.to_string()).and_then(|rom| Core::new(&rom)) else {
+        return false;
+    };
+
+    if let Some(environment) = CALLBACKS.lock().unwrap().environment {
+        let mut format = RETRO_PIXEL_FORMAT_XRGB8888;
+        if !environment(RETRO_ENVIRONMENT_SET_PIXEL_FORMAT, &mut format as *mut u32 as *mut c_void) {
+            return false;
+        }
+    }
+
+    *CORE.lock().unwrap() = Some(core);
+    true
+}
+
+#[no_mangle]
+pub extern "C" fn retro_load_game_special(_game_type: u32, _info: *const RetroGameInfo, _num_info: usize) -> bool {
+    false
+}
+
+#[no_mangle]
+pub extern "C" fn retro_unload_game() {
+    *CORE.lock().unwrap() = None;
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_region() -> u32 {
+    RETRO_REGION_NTSC
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_memory_data(id: u32) -> *mut c_void {
+    let mut core = CORE.lock().unwrap();
+    match core.as_mut() {
+        Some(core) if id == RETRO_MEMORY_SYSTEM_RAM => core.emulation.chip8_data.memory.as_mut_ptr() as *mut c_void,
+        _ => std::ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_memory_size(id: u32) -> usize {
+    match CORE.lock().unwrap().as_ref() {
+        Some(core) if id == RETRO_MEMORY_SYSTEM_RAM => core.emulation.chip8_data.memory.len(),
+        _ => 0,
+    }
+}
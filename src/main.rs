@@ -1,17 +1,549 @@
+// The launcher itself is only drawn by the SDL frontend
+#[cfg_attr(not(feature = "sdl"), allow(dead_code))]
+mod browser;
+mod cli;
+mod config;
+mod control;
+mod database;
+#[cfg(feature = "sdl")]
 mod sdl;
-mod emulation;
+mod filter;
+mod frontend;
+mod gdb;
+mod netplay;
+#[cfg(feature = "sdl")]
+mod debugger;
+#[cfg(feature = "sdl")]
+mod osd;
+mod pacing;
+mod palette;
+#[cfg_attr(not(feature = "sdl"), allow(dead_code))]
+mod text;
+mod tui;
+
+use std::{fs, io::ErrorKind, path::{Path, PathBuf}, thread, time::Duration};
+
+use chip_8_emulator::emulation;
+use serde_json::Map;
+
+use control::{ControlServer, Request};
+use emulation::{cheats::{self, RamSearch}, quirks::{Platform, Quirks}, Emulation};
+use filter::DisplayFilter;
+use frontend::{DebugCommand, Frontend, Hotkey};
+use pacing::FramePacer;
+use palette::Palette;
+
+const CONFIG_PATH: &str = "chip8.cfg";
+const DATABASE_DIR: &str = "database";
+const RECENT_PATH: &str = "recent.txt";
+// Cheat files are named after the ROM hash, see `cheats`
+const CHEATS_DIR: &str = "cheats";
+const FRAME_INTERVAL: Duration = Duration::from_nanos(1_000_000_000 / 60);
+// Emulated frames per displayed frame in slow motion
+const SLOW_MOTION_SPEED: f32 = 0.25;
 
 fn main() {
-    let mut handles = sdl::SdlHandles::new();
-    
-    let mut emulation = emulation::Emulation::new(
-        "roms/RPS.ch8",
-        &mut handles.canvas,
-        &mut handles.events
-    );
-    
-    
+    let options = match cli::parse(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", cli::USAGE);
+            return;
+        },
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    // Without a ROM the defaults apply until one is picked in the browser
+    let rom_name = options.rom.clone().unwrap_or_default();
+    let rom = options.rom.as_ref().map(|path| fs::read(path).unwrap_or_else(|err| {
+        eprintln!("Could not read {}: {}", path, err);
+        std::process::exit(1);
+    }));
+
+    // The ROM's own config can change its key bindings
+    let (settings, rom_config) = match &rom {
+        Some(rom) => rom_settings(&rom_name, rom).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }),
+        None => (database::RomSettings::default(), config::Config::empty()),
+    };
+    let (quirks, mut instructions_per_frame) = configure(&options, &settings).unwrap_or_else(|err| {
+        eprintln!("Invalid ROM settings for {}: {}", rom_name, err);
+        std::process::exit(1);
+    });
+
+    // The joining player runs with the host's seed so CXNN rolls the same on both sides
+    let mut seed = options.seed.unwrap_or_else(rand::random);
+    let mut netplay = match (&options.netplay, &rom) {
+        (Some(role), Some(rom)) => {
+            if let netplay::Role::Host(port) = role {
+                eprintln!("Waiting for the other player on port {}", port);
+            }
+            let session = netplay::Netplay::connect(role, &database::rom_hash(rom), seed, options.input_delay)
+                .unwrap_or_else(|err| {
+                    eprintln!("Netplay: {}", err);
+                    std::process::exit(1);
+                });
+            seed = session.seed;
+            Some(session)
+        },
+        _ => None,
+    };
+
+    let mut emulation = Emulation::new(Platform::Chip8, seed);
+    if let Some(rom) = &rom {
+        if let Err(err) = emulation.load_rom(rom) {
+            eprintln!("Could not load {}: {}", rom_name, err);
+            std::process::exit(1);
+        }
+    }
+    let mut rom_loaded = rom.is_some();
+    let mut cheat_path = rom.as_ref().and_then(|rom| load_cheats(rom, &mut emulation));
+    emulation.quirks = quirks;
+    emulation.trace = options.trace;
+
+    let mut gdb = options.gdb.map(|port| {
+        eprintln!("Waiting for GDB on port {}", port);
+        gdb::GdbStub::listen(port).unwrap_or_else(|err| {
+            eprintln!("GDB: {}", err);
+            std::process::exit(1);
+        })
+    });
+
+    let mut control = options.control.map(|port| ControlServer::listen(port).unwrap_or_else(|err| {
+        eprintln!("Control socket: {}", err);
+        std::process::exit(1);
+    }));
+
+    if let Some(frames) = options.headless {
+        // GDB and the control socket can hold a headless run here, frames only count while it runs
+        let mut paused = gdb.is_some() || (options.paused && control.is_some());
+        let mut frame = 0;
+        while frame < frames {
+            if let Some(gdb) = &mut gdb {
+                gdb.poll(&mut emulation, &mut paused);
+            }
+            if let Some(control) = &mut control {
+                match serve_control(control, &mut emulation, &mut paused, instructions_per_frame) {
+                    Some(Request::Quit) => break,
+                    Some(Request::LoadRom(path)) => {
+                        let result = load_game(&path, &options, &mut emulation)
+                            .map(|(speed, _)| instructions_per_frame = speed);
+                        control.reply(result.map(|_| Map::new()));
+                    },
+                    _ => {},
+                }
+            }
+            if paused {
+                thread::sleep(Duration::from_millis(1));
+                continue;
+            }
+
+            let keys = control.as_ref().map_or([false; 16], |control| control.keys);
+            emulation.keypad = keys;
+            if let Some(session) = &mut netplay {
+                match session.exchange(&keys, &emulation) {
+                    Ok(keypad) => emulation.keypad = keypad,
+                    Err(err) => {
+                        eprintln!("Netplay stopped: {}", err);
+                        // Exiting skips the drop, the other player would only see the connection reset
+                        drop(netplay);
+                        std::process::exit(1);
+                    },
+                }
+            }
+            if emulation.run_frame(instructions_per_frame) && gdb.as_ref().is_some_and(gdb::GdbStub::is_attached) {
+                paused = true;
+            }
+            frame += 1;
+        }
+        print!("{}", emulation.display.to_text());
+        return;
+    }
+
+    if rom_loaded {
+        remember_rom(Path::new(&rom_name));
+    }
+
+    let config = config::Config::load(CONFIG_PATH).unwrap_or_else(|err| {
+        eprintln!("Could not read config: {}", err);
+        std::process::exit(1);
+    });
+
+    let mut palettes = Palette::all(&config).unwrap_or_else(|err| {
+        eprintln!("Invalid palette in {}: {}", CONFIG_PATH, err);
+        std::process::exit(1);
+    });
+    let mut palette_index = 0;
+    if let Some(palette) = options.palette.clone().or(settings.palette) {
+        palette_index = palettes.iter().position(|known| *known == palette).unwrap_or_else(|| {
+            palettes.insert(0, palette);
+            0
+        });
+    }
+
+    let mut frontend: Box<dyn Frontend> = match options.frontend {
+        #[cfg(feature = "sdl")]
+        cli::FrontendKind::Sdl => {
+            let mut keymap = sdl::keymap::KeyMap::from_config(&config).unwrap_or_else(|err| {
+                eprintln!("Invalid key bindings in {}: {}", CONFIG_PATH, err);
+                std::process::exit(1);
+            });
+            keymap.bind_actions(&settings.keys);
+            if let Err(err) = keymap.apply_config(&rom_config) {
+                eprintln!("Invalid key bindings for {}: {}", rom_name, err);
+                std::process::exit(1);
+            }
+
+            let rom_dir = options.rom_dir.clone()
+                .or_else(|| {
+                    let parent = Path::new(options.rom.as_ref()?).parent()?.to_string_lossy().into_owned();
+                    Some(if parent.is_empty() { ".".to_string() } else { parent })
+                })
+                .unwrap_or_else(|| "roms".to_string());
+            let mut browser = browser::RomBrowser::new(rom_dir.into(), DATABASE_DIR, RECENT_PATH.into());
+            if !rom_loaded {
+                browser.open();
+            }
+
+            Box::new(sdl::SdlHandles::new(keymap, options.scale, options.integer_scale, browser))
+        },
+        cli::FrontendKind::Terminal(mode) => {
+            let mut keymap = tui::keymap::KeyMap::from_config(&config).unwrap_or_else(|err| {
+                eprintln!("Invalid key bindings in {}: {}", CONFIG_PATH, err);
+                std::process::exit(1);
+            });
+            keymap.bind_actions(&settings.keys);
+            if let Err(err) = keymap.apply_config(&rom_config) {
+                eprintln!("Invalid key bindings for {}: {}", rom_name, err);
+                std::process::exit(1);
+            }
+
+            Box::new(tui::TerminalFrontend::new(mode, options.scale as usize, keymap).unwrap_or_else(|err| {
+                eprintln!("Could not set up the terminal: {}", err);
+                std::process::exit(1);
+            }))
+        },
+    };
+
+    let mut filter = DisplayFilter::new(options.filter);
+    if options.debug {
+        frontend.open_debugger();
+    }
+
+    // Netplay keeps both sides running at normal speed, they wait on each other every frame.
+    // GDB and the debugger expect the program stopped when they start.
+    let mut paused = (options.paused || options.debug || gdb.is_some()) && netplay.is_none();
+    let mut slow_motion = false;
+    // Fraction of an emulated frame carried over when not running at normal speed
+    let mut frame_credit = 0.0;
+    let mut pacer = FramePacer::new(FRAME_INTERVAL, frontend.vsync(), options.frame_times);
+    // Local keypad, in netplay the other player's keys are merged in before each frame
+    let mut keys = [false; 16];
     loop {
-        emulation.execute_next_instruction();
+        let (mut advance, mut step) = (false, false);
+        let hotkeys = frontend.poll_input(&mut keys);
+        if let Some(control) = &control {
+            for (key, held) in keys.iter_mut().zip(control.keys) {
+                *key |= held;
+            }
+        }
+        for hotkey in hotkeys {
+            match hotkey {
+                Hotkey::Quit => return print_frame_times(&pacer),
+                Hotkey::Pause
+                | Hotkey::Reset
+                | Hotkey::FrameAdvance
+                | Hotkey::Step
+                | Hotkey::SlowMotion if netplay.is_some() => frontend.show_message("Not available in netplay"),
+                Hotkey::Pause => {
+                    paused = !paused;
+                    frontend.show_message(if paused { "Paused" } else { "Resumed" });
+                },
+                Hotkey::Reset => {
+                    emulation.reset();
+                    frontend.show_message("Reset");
+                },
+                Hotkey::FrameAdvance => advance = paused,
+                Hotkey::Step => step = paused,
+                Hotkey::SlowMotion => {
+                    slow_motion = !slow_motion;
+                    frontend.show_message(if slow_motion { "Slow motion on" } else { "Slow motion off" });
+                },
+                Hotkey::CyclePalette => {
+                    palette_index = (palette_index + 1) % palettes.len();
+                    frontend.show_message(&format!("Palette: {}", palettes[palette_index].name));
+                },
+                Hotkey::CycleFilter => {
+                    filter.mode = filter.mode.next();
+                    frontend.show_message(&format!("Filter: {}", filter.mode.name()));
+                },
+                Hotkey::FastForward
+                | Hotkey::Fullscreen
+                | Hotkey::IntegerScale
+                | Hotkey::Browser
+                | Hotkey::Osd
+                | Hotkey::Debugger => {}
+            }
+        }
+
+        if let Some(gdb) = &mut gdb {
+            gdb.poll(&mut emulation, &mut paused);
+        }
+
+        // A ROM asked for through the control socket gets its reply once it is loaded
+        let mut control_rom = None;
+        if let Some(control) = &mut control {
+            match serve_control(control, &mut emulation, &mut paused, instructions_per_frame) {
+                Some(Request::Quit) => return print_frame_times(&pacer),
+                Some(Request::LoadRom(path)) => control_rom = Some(path),
+                _ => {},
+            }
+        }
+
+        let requested = match control_rom {
+            Some(path) => Some((path, true)),
+            None => frontend.requested_rom().map(|path| (path, false)),
+        };
+        if let Some((path, from_control)) = requested {
+            let result = if netplay.is_some() {
+                Err("the ROM can't be changed during netplay".to_string())
+            } else {
+                load_game(&path, &options, &mut emulation)
+            };
+            match &result {
+                Ok((speed, cheats)) => {
+                    remember_rom(&path);
+                    cheat_path = cheats.clone();
+                    rom_loaded = true;
+                    instructions_per_frame = *speed;
+                    let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+                    frontend.show_message(&format!("Loaded {}", name));
+                },
+                Err(err) => frontend.show_error(&format!("Could not load {}: {}", path.display(), err)),
+            }
+            if let (Some(control), true) = (&mut control, from_control) {
+                control.reply(result.map(|_| Map::new()));
+            }
+        }
+
+        let mut cheats_changed = false;
+        for command in frontend.debug_commands() {
+            match command {
+                DebugCommand::ToggleBreakpoint(address) => {
+                    if !emulation.breakpoints.remove(&address) {
+                        emulation.breakpoints.insert(address);
+                    }
+                },
+                DebugCommand::WriteMemory(address, value) => {
+                    emulation.chip8_data.memory[address as usize & 0xFFF] = value;
+                },
+                DebugCommand::Search(None) => emulation.ram_search = Some(RamSearch::new(&emulation.chip8_data.memory)),
+                DebugCommand::Search(Some(comparison)) => {
+                    if let Some(search) = emulation.ram_search.as_mut() {
+                        search.filter(&emulation.chip8_data.memory, comparison);
+                    }
+                },
+                DebugCommand::Freeze(address) => {
+                    emulation.set_cheat(address, emulation.chip8_data.memory[address as usize & 0xFFF]);
+                    cheats_changed = true;
+                },
+                DebugCommand::SetCheat(address, value) => {
+                    emulation.set_cheat(address, value);
+                    cheats_changed = true;
+                },
+                DebugCommand::RemoveCheat(address) => {
+                    emulation.remove_cheat(address);
+                    cheats_changed = true;
+                },
+            }
+        }
+        if cheats_changed {
+            // Shows straight away even while paused
+            emulation.apply_cheats();
+            if let Some(path) = &cheat_path {
+                if let Err(err) = save_cheats(path, &emulation.cheats) {
+                    frontend.show_error(&format!("Could not save cheats: {}", err));
+                }
+            }
+        }
+
+        let running = rom_loaded && (netplay.is_some() || !frontend.menu_open());
+        let speed = if !running {
+            0.0
+        } else if netplay.is_some() {
+            1.0
+        } else if paused {
+            0.0
+        } else if frontend.held(Hotkey::FastForward) {
+            options.fast_forward as f32
+        } else if slow_motion {
+            SLOW_MOTION_SPEED
+        } else {
+            1.0
+        };
+        frontend.set_speed(speed);
+
+        let frames = if !running {
+            0
+        } else if netplay.is_some() {
+            1
+        } else if paused {
+            advance as u32
+        } else {
+            frame_credit += speed;
+            let frames = frame_credit as u32;
+            frame_credit -= frames as f32;
+            frames
+        };
+        if running && step {
+            emulation.step();
+        }
+        emulation.keypad = keys;
+        for _ in 0..frames {
+            if let Some(session) = &mut netplay {
+                match session.exchange(&keys, &emulation) {
+                    Ok(keypad) => emulation.keypad = keypad,
+                    Err(err) => {
+                        frontend.show_error(&format!("Netplay stopped: {}", err));
+                        netplay = None;
+                    },
+                }
+            }
+            if emulation.run_frame(instructions_per_frame) {
+                paused = true;
+                frontend.show_message(&format!("Breakpoint at {:03x}", emulation.chip8_data.pc));
+                break;
+            }
+        }
+
+        let frame = filter.apply(&emulation.display, &palettes[palette_index]);
+        frontend.present(&emulation, frame);
+        frontend.play_sound(
+            &emulation.chip8_data.audio_pattern,
+            emulation.chip8_data.pitch,
+            running && !paused && emulation.sound_playing()
+        );
+
+        pacer.wait();
+    }
+}
+
+fn print_frame_times(pacer: &FramePacer) {
+    if let Some(report) = pacer.report() {
+        eprintln!("{}", report);
+    }
+}
+
+// Settings are layered: ROM database, then the per-ROM override file, then command line flags
+fn rom_settings(path: &str, rom: &[u8]) -> Result<(database::RomSettings, config::Config), String> {
+    let rom_config_path = Path::new(path).with_extension("cfg");
+    let rom_config = config::Config::load(&rom_config_path.to_string_lossy())
+        .map_err(|err| format!("Could not read ROM settings: {}", err))?;
+    let mut settings = database::RomSettings::lookup(DATABASE_DIR, &database::rom_hash(rom))
+        .unwrap_or_else(|err| {
+            eprintln!("Could not read the ROM database: {}", err);
+            database::RomSettings::default()
+        });
+    settings.apply_config(&rom_config)
+        .map_err(|err| format!("Invalid ROM settings in {}: {}", rom_config_path.display(), err))?;
+
+    Ok((settings, rom_config))
+}
+
+// The quirks and instructions per frame to run a ROM with
+fn configure(options: &cli::Options, settings: &database::RomSettings) -> Result<(Quirks, u32), String> {
+    let platform = options.platform.or(settings.platform).unwrap_or(Platform::Chip8);
+    let mut quirks = platform.quirks();
+    if options.platform.is_none() {
+        settings.apply_quirks(&mut quirks)?;
+    }
+    let instructions_per_frame = options.instructions_per_frame
+        .or(settings.instructions_per_frame)
+        .unwrap_or(10);
+
+    Ok((quirks, instructions_per_frame))
+}
+
+// Swaps in another ROM with its own quirks, speed and cheats, key bindings and palette stay as they are.
+// The current game keeps running if anything goes wrong. Returns the speed and the cheat file path.
+fn load_game(path: &Path, options: &cli::Options, emulation: &mut Emulation) -> Result<(u32, Option<PathBuf>), String> {
+    let rom = fs::read(path).map_err(|err| err.to_string())?;
+    let (settings, _) = rom_settings(&path.to_string_lossy(), &rom)?;
+    let (quirks, instructions_per_frame) = configure(options, &settings)?;
+    emulation.load_rom(&rom)?;
+    emulation.quirks = quirks;
+    let cheat_path = load_cheats(&rom, emulation);
+
+    Ok((instructions_per_frame, cheat_path))
+}
+
+// Replaces the cheats and RAM search with the ROM's own cheats, and returns where they are kept.
+// A broken cheat file leaves the game running without cheats. It is moved aside first so saving
+// new cheats can't overwrite it, if that fails there is no path and nothing gets saved.
+fn load_cheats(rom: &[u8], emulation: &mut Emulation) -> Option<PathBuf> {
+    let path = Path::new(CHEATS_DIR).join(format!("{}.txt", database::rom_hash(rom)));
+    emulation.ram_search = None;
+    emulation.cheats = Vec::new();
+
+    let loaded = match fs::read_to_string(&path) {
+        Ok(text) => cheats::parse(&text).map_err(|err| format!("Invalid cheats in {}: {}", path.display(), err)),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(format!("Could not read {}: {}", path.display(), err)),
+    };
+    match loaded {
+        Ok(cheats) => emulation.cheats = cheats,
+        Err(err) => {
+            let backup = path.with_extension("txt.bak");
+            if let Err(rename_err) = fs::rename(&path, &backup) {
+                eprintln!("{}, cheats won't be saved: {}", err, rename_err);
+                return None;
+            }
+            eprintln!("{}, moved it to {}", err, backup.display());
+        },
+    }
+    Some(path)
+}
+
+fn save_cheats(path: &Path, cheats: &[cheats::Cheat]) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| format!("{}: {}", dir.display(), err))?;
+    }
+    fs::write(path, cheats::to_text(cheats)).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+// Recent games are a nicety, failing to record one is not worth stopping for
+fn remember_rom(path: &Path) {
+    if let Err(err) = browser::remember(Path::new(RECENT_PATH), path) {
+        eprintln!("Could not update recent games: {}", err);
+    }
+}
+
+// Carries out what the control socket asked for. Quitting and loading a ROM are handed back
+// as they reach further into the main loop, a ROM load still needs its reply.
+fn serve_control(control: &mut ControlServer, emulation: &mut Emulation, paused: &mut bool, instructions_per_frame: u32) -> Option<Request> {
+    while let Some(request) = control.next_request(emulation) {
+        match request {
+            Request::Pause(pause) => *paused = pause,
+            // Stepped frames only see the keys held through the socket
+            Request::Step(frames) => {
+                emulation.keypad = control.keys;
+                for _ in 0..frames {
+                    if emulation.run_frame(instructions_per_frame) {
+                        break;
+                    }
+                }
+            },
+            Request::Quit => {
+                control.reply(Ok(Map::new()));
+                return Some(Request::Quit);
+            },
+            Request::LoadRom(path) => return Some(Request::LoadRom(path)),
+        }
+        control.reply(Ok(Map::new()));
     }
+    None
 }
@@ -0,0 +1,305 @@
+use std::{
+    collections::VecDeque,
+    io::{Read, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::emulation::Emulation;
+
+// Two player netplay over TCP. Both sides run the same ROM with the same seed and send
+// each other their keypad every frame. A frame only runs once the other side's keys for it
+// have arrived, so both machines see the same input and stay in lockstep. The keys pressed
+// on frame N are sent for frame N + `delay`, so by the time the other side needs them they
+// have been on the way for `delay` frames and a round trip shorter than that never stalls.
+// Each message also carries a hash of the machine state on the frame it was sent, a mismatch
+// means the two have desynced.
+
+const MAGIC: &[u8; 4] = b"C8N1";
+// How long to wait for the other player before giving up
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Role {
+    // Listens on a port, and picks the seed and input delay
+    Host(u16),
+    // `host:port` to connect to
+    Join(String),
+}
+
+pub struct Netplay {
+    stream: TcpStream,
+    // The next frame to run
+    frame: u64,
+    delay: u64,
+    // Our keypad bitmasks for the coming frames, the front one is for `frame`
+    local: VecDeque<u16>,
+    // Our state hashes for the last `delay` frames, the other side's arrive that much later
+    hashes: VecDeque<u64>,
+    // Both sides have to seed CXNN alike, the joining side takes the host's
+    pub seed: u64,
+}
+
+impl Netplay {
+
+    // Waits for the other player and checks they are running the same ROM
+    pub fn connect(role: &Role, rom_hash: &str, seed: u64, delay: u32) -> Result<Self, String> {
+        match role {
+            Role::Host(port) => Self::host(*port, rom_hash, seed, delay),
+            Role::Join(address) => Self::join(address, rom_hash),
+        }
+    }
+
+    fn host(port: u16, rom_hash: &str, seed: u64, delay: u32) -> Result<Self, String> {
+        let listener = TcpListener::bind(("0.0.0.0", port)).map_err(|err| format!("port {}: {}", port, err))?;
+        let (mut stream, _) = listener.accept().map_err(|err| err.to_string())?;
+        setup(&stream)?;
+
+        let mut hello = MAGIC.to_vec();
+        hello.extend_from_slice(rom_hash.as_bytes());
+        hello.extend_from_slice(&seed.to_le_bytes());
+        hello.extend_from_slice(&delay.to_le_bytes());
+        stream.write_all(&hello).map_err(|err| err.to_string())?;
+
+        let reply = read_exact::<{ MAGIC.len() + 1 }>(&mut stream)?;
+        if reply[..MAGIC.len()] != MAGIC[..] {
+            return Err("the other side is not a compatible emulator".to_string());
+        }
+        if reply[MAGIC.len()] == 0 {
+            return Err("the other player has a different ROM loaded".to_string());
+        }
+
+        Ok(Self::new(stream, seed, delay))
+    }
+
+    // The host may not be listening yet, so connecting is retried until TIMEOUT
+    fn join(address: &str, rom_hash: &str) -> Result<Self, String> {
+        let start = Instant::now();
+        let mut stream = loop {
+            match TcpStream::connect(address) {
+                Ok(stream) => break stream,
+                Err(err) if start.elapsed() > TIMEOUT => return Err(format!("{}: {}", address, err)),
+                Err(_) => thread::sleep(Duration::from_millis(100)),
+            }
+        };
+        setup(&stream)?;
+
+        let magic = read_exact::<{ MAGIC.len() }>(&mut stream)?;
+        if magic != *MAGIC {
+            return Err("the other side is not a compatible emulator".to_string());
+        }
+        let host_rom_hash = read_exact::<40>(&mut stream)?;
+        let seed = u64::from_le_bytes(read_exact(&mut stream)?);
+        let delay = u32::from_le_bytes(read_exact(&mut stream)?);
+
+        let same_rom = host_rom_hash[..] == *rom_hash.as_bytes();
+        let mut reply = MAGIC.to_vec();
+        reply.push(same_rom as u8);
+        stream.write_all(&reply).map_err(|err| err.to_string())?;
+        if !same_rom {
+            return Err("the other player has a different ROM loaded".to_string());
+        }
+
+        Ok(Self::new(stream, seed, delay))
+    }
+
+    fn new(stream: TcpStream, seed: u64, delay: u32) -> Self {
+        Self {
+            stream,
+            frame: 0,
+            delay: delay as u64,
+            local: VecDeque::from(vec![0; delay as usize]),
+            hashes: VecDeque::new(),
+            seed,
+        }
+    }
+
+    // Sends our keys for `delay` frames from now and waits for the other player's keys for this
+    // frame, which they sent `delay` frames ago. Returns the keypad to run the next frame with,
+    // both players' keys are merged so each can use their own half of it.
+    pub fn exchange(&mut self, keys: &[bool; 16], emulation: &Emulation) -> Result<[bool; 16], String> {
+        let mask = keys.iter().enumerate().fold(0u16, |mask, (key, pressed)| mask | (*pressed as u16) << key);
+        let hash = state_hash(emulation);
+
+        let mut message = Vec::with_capacity(18);
+        message.extend_from_slice(&(self.frame + self.delay).to_le_bytes());
+        message.extend_from_slice(&mask.to_le_bytes());
+        message.extend_from_slice(&hash.to_le_bytes());
+        self.stream.write_all(&message).map_err(|err| format!("connection lost: {}", err))?;
+        self.local.push_back(mask);
+        self.hashes.push_back(hash);
+
+        // Nobody pressed anything before the session started
+        let mut remote_mask = 0;
+        if self.frame >= self.delay {
+            let frame = u64::from_le_bytes(read_exact(&mut self.stream)?);
+            remote_mask = u16::from_le_bytes(read_exact(&mut self.stream)?);
+            let remote_hash = u64::from_le_bytes(read_exact(&mut self.stream)?);
+            if frame != self.frame {
+                return Err(format!("out of step, expected keys for frame {} but got frame {}", self.frame, frame));
+            }
+            if self.hashes.pop_front() != Some(remote_hash) {
+                return Err(format!("desync at frame {}", self.frame - self.delay));
+            }
+        }
+
+        let merged = self.local.pop_front().unwrap() | remote_mask;
+        self.frame += 1;
+
+        Ok(std::array::from_fn(|key| merged & 1 << key != 0))
+    }
+
+}
+
+// The other side's messages for our last `delay` frames are never read. Closing with them
+// unread would reset the connection before the other side has read ours, so this waits for
+// it to close as well.
+impl Drop for Netplay {
+    fn drop(&mut self) {
+        let _ = self.stream.shutdown(Shutdown::Write);
+        let _ = std::io::copy(&mut self.stream, &mut std::io::sink());
+    }
+}
+
+fn setup(stream: &TcpStream) -> Result<(), String> {
+    stream.set_nodelay(true).map_err(|err| err.to_string())?;
+    stream.set_read_timeout(Some(TIMEOUT)).map_err(|err| err.to_string())
+}
+
+fn read_exact<const N: usize>(stream: &mut TcpStream) -> Result<[u8; N], String> {
+    let mut buffer = [0; N];
+    stream.read_exact(&mut buffer).map_err(|err| format!("connection lost: {}", err))?;
+    Ok(buffer)
+}
+
+// Save states cover everything that affects how the game runs
+fn state_hash(emulation: &Emulation) -> u64 {
+    let digest = sha1_smol::Sha1::from(emulation.save_state()).digest().bytes();
+    u64::from_le_bytes(digest[..8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emulation::quirks::Platform;
+
+    const DELAY: u32 = 2;
+    const FRAMES: usize = 6;
+    // Each way, for the sessions from `slow_pair`
+    const LATENCY: Duration = Duration::from_millis(10);
+
+    fn socket_pair() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let join = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (host, _) = listener.accept().unwrap();
+        (host, join)
+    }
+
+    fn pair() -> (Netplay, Netplay) {
+        let (host, join) = socket_pair();
+        setup(&host).unwrap();
+        setup(&join).unwrap();
+        (Netplay::new(host, 0, DELAY), Netplay::new(join, 0, DELAY))
+    }
+
+    // Passes bytes on `latency` after they were sent
+    fn forward(mut from: TcpStream, mut to: TcpStream, latency: Duration) {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        thread::spawn(move || {
+            let mut buffer = [0; 256];
+            while let Ok(length @ 1..) = from.read(&mut buffer) {
+                sender.send((Instant::now() + latency, buffer[..length].to_vec())).unwrap();
+            }
+        });
+        thread::spawn(move || {
+            for (due, bytes) in receiver {
+                thread::sleep(due.saturating_duration_since(Instant::now()));
+                if to.write_all(&bytes).is_err() {
+                    break;
+                }
+            }
+            let _ = to.shutdown(Shutdown::Write);
+        });
+    }
+
+    // Two sessions whose messages take `latency` to arrive
+    fn slow_pair(latency: Duration, delay: u32) -> (Netplay, Netplay) {
+        let (host, host_end) = socket_pair();
+        let (join, join_end) = socket_pair();
+        forward(host_end.try_clone().unwrap(), join_end.try_clone().unwrap(), latency);
+        forward(join_end, host_end, latency);
+        setup(&host).unwrap();
+        setup(&join).unwrap();
+        (Netplay::new(host, 0, delay), Netplay::new(join, 0, delay))
+    }
+
+    // Each frame one key, the host on the left half of the keypad and the joiner on the right
+    fn keys(key: usize) -> [bool; 16] {
+        std::array::from_fn(|n| n == key)
+    }
+
+    fn play(mut session: Netplay, emulation: Emulation, first_key: usize) -> thread::JoinHandle<Result<Vec<[bool; 16]>, String>> {
+        thread::spawn(move || {
+            (0..FRAMES).map(|frame| session.exchange(&keys(first_key + frame), &emulation)).collect()
+        })
+    }
+
+    #[test]
+    fn keys_arrive_delay_frames_later_on_both_sides() {
+        let (host, join) = pair();
+        let host = play(host, Emulation::new(Platform::Chip8, 0), 0);
+        let join = play(join, Emulation::new(Platform::Chip8, 0), 8);
+        let host = host.join().unwrap().unwrap();
+        let join = join.join().unwrap().unwrap();
+
+        assert_eq!(host, join);
+        for (frame, keypad) in host.iter().enumerate() {
+            let expected: [bool; 16] = match frame.checked_sub(DELAY as usize) {
+                Some(sent) => std::array::from_fn(|n| n == sent || n == 8 + sent),
+                None => [false; 16],
+            };
+            assert_eq!(*keypad, expected, "frame {}", frame);
+        }
+    }
+
+    #[test]
+    fn different_states_desync() {
+        let (host, join) = pair();
+        let mut other = Emulation::new(Platform::Chip8, 0);
+        other.chip8_data.var_registers[0] = 1;
+        let host = play(host, Emulation::new(Platform::Chip8, 0), 0);
+        let join = play(join, other, 8);
+
+        assert_eq!(host.join().unwrap(), Err("desync at frame 0".to_string()));
+        assert_eq!(join.join().unwrap(), Err("desync at frame 0".to_string()));
+    }
+
+    // The longest either side waited in `exchange`, over frames that each take `FRAME_TIME`
+    fn longest_stall(delay: u32) -> Duration {
+        const FRAME_TIME: Duration = Duration::from_millis(15);
+        let sessions = slow_pair(LATENCY, delay);
+        let players = [sessions.0, sessions.1].map(|mut session| thread::spawn(move || {
+            let emulation = Emulation::new(Platform::Chip8, 0);
+            (0..20).map(|_| {
+                let start = Instant::now();
+                session.exchange(&[false; 16], &emulation).unwrap();
+                let stall = start.elapsed();
+                thread::sleep(FRAME_TIME);
+                stall
+            }).max().unwrap()
+        }));
+        players.map(|player| player.join().unwrap()).into_iter().max().unwrap()
+    }
+
+    #[test]
+    fn no_delay_waits_for_the_network() {
+        assert!(longest_stall(0) >= LATENCY);
+    }
+
+    #[test]
+    fn delay_hides_the_latency() {
+        let stall = longest_stall(DELAY);
+        assert!(stall < LATENCY / 2, "{:?}", stall);
+    }
+}
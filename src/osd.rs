@@ -0,0 +1,131 @@
+use std::time::{Duration, Instant};
+
+use crate::{emulation::{script::OverlayText, Emulation}, palette::Rgb, text};
+
+// Status line, transient messages and script overlay text drawn over the game. The frame
+// is scaled up first so the text is sharper than CHIP-8 pixels.
+
+// Width the game is scaled up to, in whole multiples of the display width
+const TARGET_WIDTH: usize = 320;
+const MESSAGE_TIME: Duration = Duration::from_secs(2);
+const MAX_MESSAGES: usize = 3;
+const MEASURE_INTERVAL: Duration = Duration::from_millis(500);
+
+const TEXT: Rgb = (0xFF, 0xFF, 0xFF);
+const BOX: Rgb = (0x20, 0x20, 0x20);
+
+pub struct Osd {
+    pub show_stats: bool,
+    messages: Vec<(String, Instant)>,
+    // Copied from the emulation each frame, placed in display pixels
+    overlay: Vec<OverlayText>,
+    speed: f32,
+    // Counters at the start of the current measurement, see MEASURE_INTERVAL
+    measure_start: Instant,
+    frames: u32,
+    instructions: u64,
+    fps: f32,
+    instructions_per_second: f32,
+    output: Vec<Rgb>,
+}
+
+impl Osd {
+
+    pub fn new() -> Self {
+        Self {
+            show_stats: false,
+            messages: Vec::new(),
+            overlay: Vec::new(),
+            speed: 1.0,
+            measure_start: Instant::now(),
+            frames: 0,
+            instructions: 0,
+            fps: 0.0,
+            instructions_per_second: 0.0,
+            output: Vec::new(),
+        }
+    }
+
+    pub fn message(&mut self, text: &str) {
+        self.messages.push((text.to_string(), Instant::now()));
+        if self.messages.len() > MAX_MESSAGES {
+            self.messages.remove(0);
+        }
+    }
+
+    // 0 while paused
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    // Called once per presented frame to keep the FPS and IPS figures current
+    pub fn tick(&mut self, emulation: &Emulation) {
+        self.frames += 1;
+
+        let elapsed = self.measure_start.elapsed();
+        if elapsed >= MEASURE_INTERVAL {
+            let seconds = elapsed.as_secs_f32();
+            let instructions = emulation.instructions_executed.saturating_sub(self.instructions);
+            self.fps = self.frames as f32 / seconds;
+            self.instructions_per_second = instructions as f32 / seconds;
+
+            self.measure_start = Instant::now();
+            self.frames = 0;
+            self.instructions = emulation.instructions_executed;
+        }
+
+        self.messages.retain(|(_, shown)| shown.elapsed() < MESSAGE_TIME);
+        self.overlay.clear();
+        self.overlay.extend_from_slice(emulation.overlay());
+    }
+
+    // Nothing to draw means the game frame can be shown as it is
+    pub fn is_active(&self) -> bool {
+        self.show_stats || !self.messages.is_empty() || !self.overlay.is_empty()
+    }
+
+    // Returns the scaled up frame with the overlay on top, and its size
+    pub fn composite(&mut self, width: usize, height: usize, frame: &[Rgb]) -> (usize, usize, &[Rgb]) {
+        let scale = (TARGET_WIDTH / width).max(1);
+        let (out_width, out_height) = (width * scale, height * scale);
+
+        self.output.resize(out_width * out_height, (0, 0, 0));
+        for y in 0..out_height {
+            let row = &frame[(y / scale) * width..(y / scale + 1) * width];
+            for (x, pixel) in self.output[y * out_width..(y + 1) * out_width].iter_mut().enumerate() {
+                *pixel = row[x / scale];
+            }
+        }
+
+        for item in self.overlay.clone() {
+            self.draw_line(out_width, item.x.saturating_mul(scale), item.y.saturating_mul(scale), &item.text);
+        }
+
+        if self.show_stats {
+            let speed = if self.speed == 0.0 {
+                "PAUSED".to_string()
+            } else {
+                format!("X{}", self.speed)
+            };
+            let stats = format!("{:.0} FPS  {:.0} IPS  {}", self.fps, self.instructions_per_second, speed);
+            self.draw_line(out_width, 0, 0, &stats);
+        }
+
+        let bottom = out_height / text::CELL_HEIGHT;
+        let lines: Vec<String> = self.messages.iter().map(|(text, _)| text.clone()).collect();
+        for (index, line) in lines.iter().enumerate() {
+            let row = bottom - lines.len() + index;
+            self.draw_line(out_width, 0, row * text::CELL_HEIGHT, line);
+        }
+
+        (out_width, out_height, &self.output)
+    }
+
+    // Text on a dark box so it stays readable over lit pixels
+    fn draw_line(&mut self, width: usize, x: usize, y: usize, line: &str) {
+        let box_width = line.chars().count() * text::CELL_WIDTH + 1;
+        text::fill_rect(&mut self.output, width, x, y, box_width, text::CELL_HEIGHT, BOX);
+        text::draw(&mut self.output, width, x + 1, y + 1, line, TEXT);
+    }
+
+}
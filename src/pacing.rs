@@ -0,0 +1,176 @@
+use std::{thread, time::{Duration, Instant}};
+
+// Holds the main loop to 60 frames a second for the frontends that don't wait for vsync,
+// the terminal and headless runs. With vsync the monitor is the clock, the pacer only keeps
+// the frame times.
+pub struct FramePacer<C: Clock = SystemClock> {
+    clock: C,
+    interval: Duration,
+    vsync: bool,
+    next_frame: Instant,
+    last_frame: Instant,
+    // Only kept with --frame-times
+    stats: Option<FrameStats>,
+}
+
+#[derive(Default)]
+struct FrameStats {
+    frames: u32,
+    total: Duration,
+    worst: Duration,
+    // Frames whose work ran past the deadline, the schedule restarts from there
+    late: u32,
+}
+
+// Time as the pacer sees it, so the tests can run it without sleeping
+pub trait Clock {
+    fn now(&self) -> Instant;
+    fn sleep(&mut self, duration: Duration);
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+impl FramePacer {
+
+    pub fn new(interval: Duration, vsync: bool, record: bool) -> Self {
+        Self::with_clock(SystemClock, interval, vsync, record)
+    }
+
+}
+
+impl<C: Clock> FramePacer<C> {
+
+    pub fn with_clock(clock: C, interval: Duration, vsync: bool, record: bool) -> Self {
+        let now = clock.now();
+        Self {
+            clock,
+            interval,
+            vsync,
+            next_frame: now,
+            last_frame: now,
+            stats: record.then(FrameStats::default),
+        }
+    }
+
+    // Sleeps until the next frame is due. A late frame moves the schedule rather than
+    // running the following ones back to back to catch up. With vsync `present` has already
+    // waited, and no frame counts as late.
+    pub fn wait(&mut self) {
+        self.next_frame += self.interval;
+        let now = self.clock.now();
+        let late = !self.vsync && self.next_frame <= now;
+        if late || self.vsync {
+            self.next_frame = now;
+        } else {
+            self.clock.sleep(self.next_frame - now);
+        }
+
+        if let Some(stats) = &mut self.stats {
+            let now = self.clock.now();
+            let frame_time = now - self.last_frame;
+            stats.frames += 1;
+            stats.total += frame_time;
+            stats.worst = stats.worst.max(frame_time);
+            stats.late += late as u32;
+        }
+        self.last_frame = self.clock.now();
+    }
+
+    pub fn report(&self) -> Option<String> {
+        let stats = self.stats.as_ref().filter(|stats| stats.frames > 0)?;
+        let millis = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        Some(format!(
+            "{} frames, mean {:.2} ms, worst {:.2} ms, {} late",
+            stats.frames,
+            millis(stats.total) / stats.frames as f64,
+            millis(stats.worst),
+            stats.late
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_millis(10);
+
+    // Only moves when the pacer sleeps or a test spends time on a frame's work
+    struct FakeClock {
+        now: Instant,
+        sleeps: Vec<Duration>,
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            self.now
+        }
+
+        fn sleep(&mut self, duration: Duration) {
+            self.now += duration;
+            self.sleeps.push(duration);
+        }
+    }
+
+    fn pacer(vsync: bool, record: bool) -> FramePacer<FakeClock> {
+        let clock = FakeClock { now: Instant::now(), sleeps: Vec::new() };
+        FramePacer::with_clock(clock, INTERVAL, vsync, record)
+    }
+
+    // Runs a frame whose work takes `millis`
+    fn frame(pacer: &mut FramePacer<FakeClock>, millis: u64) {
+        pacer.clock.now += Duration::from_millis(millis);
+        pacer.wait();
+    }
+
+    #[test]
+    fn holds_the_interval() {
+        let mut pacer = pacer(false, true);
+        for millis in [2, 7, 0, 9] {
+            frame(&mut pacer, millis);
+        }
+        let sleeps = [8, 3, 10, 1].map(Duration::from_millis);
+        assert_eq!(pacer.clock.sleeps, sleeps);
+        assert_eq!(pacer.report().unwrap(), "4 frames, mean 10.00 ms, worst 10.00 ms, 0 late");
+    }
+
+    #[test]
+    fn late_frames_dont_burst() {
+        let mut pacer = pacer(false, true);
+        frame(&mut pacer, 50);
+        // Catching up would run these without sleeping
+        for _ in 0..3 {
+            frame(&mut pacer, 2);
+        }
+        let sleeps = [8, 8, 8].map(Duration::from_millis);
+        assert_eq!(pacer.clock.sleeps, sleeps);
+        assert_eq!(pacer.report().unwrap(), "4 frames, mean 20.00 ms, worst 50.00 ms, 1 late");
+    }
+
+    #[test]
+    fn vsync_doesnt_sleep() {
+        let mut pacer = pacer(true, true);
+        for millis in [16, 17, 30] {
+            frame(&mut pacer, millis);
+        }
+        assert!(pacer.clock.sleeps.is_empty());
+        assert_eq!(pacer.report().unwrap(), "3 frames, mean 21.00 ms, worst 30.00 ms, 0 late");
+    }
+
+    #[test]
+    fn no_report_without_recording() {
+        let mut pacer = pacer(false, false);
+        frame(&mut pacer, 2);
+        assert!(pacer.report().is_none());
+    }
+}
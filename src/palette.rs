@@ -0,0 +1,82 @@
+use crate::config::Config;
+
+pub type Rgb = (u8, u8, u8);
+
+// Colours are indexed by a pixel's bitplane bits: off, plane 1, plane 2, both planes
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Palette {
+    pub name: String,
+    pub colors: [Rgb; 4],
+}
+
+const BUILTIN: [(&str, [Rgb; 4]); 5] = [
+    ("classic", [(0x00, 0x00, 0x00), (0xFF, 0xFF, 0xFF), (0xAA, 0xAA, 0xAA), (0x55, 0x55, 0x55)]),
+    ("green", [(0x0A, 0x14, 0x0A), (0x33, 0xFF, 0x66), (0x1A, 0x99, 0x40), (0x99, 0xFF, 0xB3)]),
+    ("amber", [(0x14, 0x0A, 0x00), (0xFF, 0xB0, 0x00), (0x99, 0x66, 0x00), (0xFF, 0xD8, 0x80)]),
+    ("lcd", [(0x9B, 0xBC, 0x0F), (0x0F, 0x38, 0x0F), (0x30, 0x62, 0x30), (0x8B, 0xAC, 0x0F)]),
+    ("octo", [(0x99, 0x66, 0x00), (0xFF, 0xCC, 0x00), (0xFF, 0x66, 0x00), (0x66, 0x22, 0x00)]),
+];
+
+impl Palette {
+
+    pub fn builtin() -> Vec<Self> {
+        BUILTIN.iter()
+            .map(|(name, colors)| Self {
+                name: name.to_string(),
+                colors: *colors,
+            })
+            .collect()
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::builtin().into_iter().find(|palette| palette.name.eq_ignore_ascii_case(name))
+    }
+
+    // Either the name of a built in palette or a list of colours such as `000000,ffffff`.
+    // With only two colours the plane 2 combinations reuse the plane 1 colour.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        if let Some(palette) = Self::from_name(text.trim()) {
+            return Ok(palette);
+        }
+        Self::from_colors("custom", &text.split(',').collect::<Vec<_>>())
+    }
+
+    pub fn from_colors(name: &str, colors: &[&str]) -> Result<Self, String> {
+        let colors = colors.iter()
+            .map(|color| parse_color(color))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let colors = match colors[..] {
+            [off, on] => [off, on, on, on],
+            [off, plane_1, plane_2, both] => [off, plane_1, plane_2, both],
+            _ => return Err(format!("a palette needs 2 or 4 colours, got {}", colors.len())),
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            colors,
+        })
+    }
+
+    // The built in palettes followed by the `[palette]` section of the config, if any
+    pub fn all(config: &Config) -> Result<Vec<Self>, String> {
+        let mut palettes = Self::builtin();
+        if let Some(colors) = config.get("palette", "colors") {
+            let name = config.get("palette", "name").unwrap_or("custom");
+            palettes.push(Self::from_colors(name, &colors.split(',').collect::<Vec<_>>())?);
+        }
+        Ok(palettes)
+    }
+
+}
+
+pub fn parse_color(text: &str) -> Result<Rgb, String> {
+    let hex = text.trim().trim_start_matches('#');
+    let valid = hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit());
+    if !valid {
+        return Err(format!("`{}` is not a RRGGBB colour", text.trim()));
+    }
+
+    let channel = |start| u8::from_str_radix(&hex[start..start+2], 16).unwrap();
+    Ok((channel(0), channel(2), channel(4)))
+}
@@ -1,37 +1,224 @@
 extern crate sdl2;
 
-use sdl2::{ Sdl, VideoSubsystem, video::Window };
+use std::path::PathBuf;
 
-use self::{canvas::CanvasUtils, events::EventHandler};
+use sdl2::{messagebox::{self, MessageBoxFlag}, Sdl, VideoSubsystem};
 
+use crate::{
+    browser::{self, RomBrowser},
+    debugger::{self, Click, DebugPanel},
+    emulation::{display, Emulation},
+    frontend::{DebugCommand, Frontend, Hotkey},
+    osd::Osd,
+    palette::Rgb,
+};
+
+use self::{audio::AudioHandler, canvas::CanvasUtils, events::{DebuggerInput, EventHandler}, keymap::KeyMap};
+
+// The debug panel is shown at this many window pixels per panel pixel
+const DEBUGGER_SCALE: u32 = 2;
+
+pub mod audio;
 pub mod canvas;
 pub mod events;
-
-pub const PIXEL_SIZE: u32 = 10;
+pub mod keymap;
 
 pub struct SdlHandles {
-    pub sdl_context: Sdl,
+    // Never used after setup, but SDL shuts down once the last handle to it is dropped
+    _sdl_context: Sdl,
     pub video_subsystem: VideoSubsystem,
     pub canvas: CanvasUtils,
     pub events: EventHandler,
+    pub audio: AudioHandler,
+    pub browser: RomBrowser,
+    pub osd: Osd,
+    // Its own window, opened on demand
+    pub debugger: Option<CanvasUtils>,
+    pub debug_panel: DebugPanel,
+    debug_commands: Vec<DebugCommand>,
+    // Picked in the browser, waiting for the main loop to load it
+    picked_rom: Option<PathBuf>,
 }
 
 impl SdlHandles {
 
-    pub fn new() -> Self {
+    pub fn new(keymap: KeyMap, scale: u32, integer_scale: bool, browser: RomBrowser) -> Self {
         let sdl_context = sdl2::init().unwrap();
         let video_subsystem = sdl_context.video().unwrap();
-        let window = video_subsystem.window("Chip-8 Emulator", 64*PIXEL_SIZE, 32*PIXEL_SIZE)
+        let window = video_subsystem.window(
+                "Chip-8 Emulator",
+                display::WIDTH as u32 * scale,
+                display::HEIGHT as u32 * scale
+            )
+            .resizable()
             .build()
             .unwrap();
-        let canvas = CanvasUtils::new(window);
-        let events = EventHandler::new(&sdl_context);
+        let canvas = CanvasUtils::new(window, integer_scale, true);
+        let events = EventHandler::new(&sdl_context, keymap);
+        let audio = AudioHandler::new(&sdl_context);
 
         Self {
-            sdl_context,
+            _sdl_context: sdl_context,
             video_subsystem,
             canvas,
             events,
+            audio,
+            browser,
+            osd: Osd::new(),
+            debugger: None,
+            debug_panel: DebugPanel::new(),
+            debug_commands: Vec::new(),
+            picked_rom: None,
+        }
+    }
+
+    fn toggle_debugger(&mut self) {
+        if self.debugger.take().is_some() {
+            self.events.debugger_window = None;
+            return;
+        }
+
+        let window = self.video_subsystem.window(
+                "Chip-8 Debugger",
+                debugger::WIDTH as u32 * DEBUGGER_SCALE,
+                debugger::HEIGHT as u32 * DEBUGGER_SCALE
+            )
+            .resizable()
+            .build()
+            .unwrap();
+        self.events.debugger_window = Some(window.id());
+        self.debugger = Some(CanvasUtils::new(window, true, false));
+    }
+
+}
+
+impl Frontend for SdlHandles {
+
+    fn poll_input(&mut self, keypad: &mut [bool; 16]) -> Vec<Hotkey> {
+        let mut hotkeys = self.events.update_events();
+        hotkeys.retain(|hotkey| match hotkey {
+            Hotkey::Fullscreen => {
+                self.canvas.toggle_fullscreen();
+                false
+            },
+            Hotkey::IntegerScale => {
+                self.canvas.toggle_integer_scale();
+                false
+            },
+            Hotkey::Debugger => {
+                self.toggle_debugger();
+                false
+            },
+            Hotkey::Osd => {
+                self.osd.show_stats = !self.osd.show_stats;
+                false
+            },
+            Hotkey::Browser => {
+                if self.browser.is_open() {
+                    self.browser.close();
+                } else {
+                    self.browser.open();
+                }
+                false
+            },
+            _ => true,
+        });
+
+        for key in self.events.menu_keys.drain(..) {
+            if let Some(path) = self.browser.handle_key(key) {
+                self.picked_rom = Some(path);
+            }
+        }
+        self.events.menu_open = self.browser.is_open();
+
+        for input in self.events.debugger_input.drain(..) {
+            match input {
+                DebuggerInput::Click(x, y) => match self.debug_panel.click(x, y) {
+                    Some(Click::Hotkey(hotkey)) => hotkeys.push(hotkey),
+                    Some(Click::Command(command)) => self.debug_commands.push(command),
+                    None => {}
+                },
+                DebuggerInput::Scroll(notches) => self.debug_panel.scroll(notches),
+                DebuggerInput::Cursor(movement) => self.debug_panel.move_cursor(movement),
+                DebuggerInput::Text(text) => self.debug_commands.extend(self.debug_panel.type_text(&text)),
+            }
+        }
+
+        *keypad = if self.browser.is_open() { [false; 16] } else { self.events.keys };
+        hotkeys
+    }
+
+    fn held(&self, hotkey: Hotkey) -> bool {
+        self.events.held_hotkeys.contains(&hotkey)
+    }
+
+    fn present(&mut self, emulation: &Emulation, frame: &[Rgb]) {
+        self.osd.tick(emulation);
+        let (width, height) = (emulation.display.width, emulation.display.height);
+        if self.browser.is_open() {
+            self.canvas.draw_frame(browser::WIDTH, browser::HEIGHT, self.browser.draw());
+        } else if self.osd.is_active() {
+            let (width, height, frame) = self.osd.composite(width, height, frame);
+            self.canvas.draw_frame(width, height, frame);
+        } else {
+            self.canvas.draw_frame(width, height, frame);
+        }
+        self.canvas.present();
+
+        if let Some(debugger) = self.debugger.as_mut() {
+            debugger.draw_frame(debugger::WIDTH, debugger::HEIGHT, self.debug_panel.draw(emulation));
+            debugger.present();
+        }
+    }
+
+    fn vsync(&self) -> bool {
+        true
+    }
+
+    fn play_sound(&mut self, pattern: &[u8; 16], pitch: u8, playing: bool) {
+        self.audio.update(pattern, pitch, playing);
+    }
+
+    fn requested_rom(&mut self) -> Option<PathBuf> {
+        if let Some(path) = self.events.dropped_file.take() {
+            self.browser.close();
+            return Some(path);
+        }
+        self.picked_rom.take()
+    }
+
+    fn open_debugger(&mut self) {
+        if self.debugger.is_none() {
+            self.toggle_debugger();
+        }
+    }
+
+    fn debug_commands(&mut self) -> Vec<DebugCommand> {
+        std::mem::take(&mut self.debug_commands)
+    }
+
+    fn menu_open(&self) -> bool {
+        self.browser.is_open()
+    }
+
+    fn show_message(&mut self, message: &str) {
+        self.osd.message(message);
+    }
+
+    fn set_speed(&mut self, speed: f32) {
+        self.osd.set_speed(speed);
+        self.debug_panel.paused = speed == 0.0;
+    }
+
+    fn show_error(&mut self, message: &str) {
+        let shown = messagebox::show_simple_message_box(
+            MessageBoxFlag::ERROR,
+            "Chip-8 Emulator",
+            message,
+            self.canvas.window()
+        );
+        if shown.is_err() {
+            eprintln!("{}", message);
         }
     }
 
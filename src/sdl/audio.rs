@@ -0,0 +1,56 @@
+extern crate sdl2;
+
+use sdl2::{audio::{AudioCallback, AudioDevice, AudioSpecDesired}, Sdl};
+
+use crate::emulation::sound::PatternWave;
+
+// Only asked for, the device may be opened at another rate
+const SAMPLE_RATE: i32 = 44100;
+const VOLUME: f32 = 0.15;
+
+pub struct Speaker {
+    wave: PatternWave,
+    // The rate the device was actually opened at
+    sample_rate: u32,
+}
+
+impl AudioCallback for Speaker {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        self.wave.fill(out, self.sample_rate, VOLUME);
+    }
+}
+
+pub struct AudioHandler {
+    device: AudioDevice<Speaker>,
+}
+
+impl AudioHandler {
+
+    pub fn new(sdl_context: &Sdl) -> Self {
+        let audio_subsystem = sdl_context.audio().unwrap();
+        let desired_spec = AudioSpecDesired {
+            freq: Some(SAMPLE_RATE),
+            channels: Some(1),
+            samples: None,
+        };
+
+        let device = audio_subsystem.open_playback(None, &desired_spec, |spec| {
+            Speaker {
+                wave: PatternWave::new(),
+                sample_rate: spec.freq as u32,
+            }
+        }).unwrap();
+        device.resume();
+
+        Self {
+            device,
+        }
+    }
+
+    pub fn update(&mut self, pattern: &[u8; 16], pitch: u8, playing: bool) {
+        self.device.lock().wave.update(pattern, pitch, playing);
+    }
+
+}
@@ -1,63 +1,97 @@
 extern crate sdl2;
 
-use sdl2::{video::Window, pixels::Color, rect::{Point, Rect}};
+use sdl2::{
+    pixels::{Color, PixelFormatEnum},
+    render::{Texture, TextureCreator},
+    video::{FullscreenType, Window, WindowContext},
+};
 
-use super::PIXEL_SIZE;
+use crate::palette::Rgb;
 
 pub struct CanvasUtils {
     handle: sdl2::render::Canvas<Window>,
-    pixel_data: [[bool; 64]; 32],
+    texture_creator: TextureCreator<WindowContext>,
+    // The framebuffer is streamed into this once per frame, sized to the display resolution
+    texture: Option<Texture>,
 }
 
 impl CanvasUtils {
     
-    pub fn new(window: Window) -> Self {
-        let handle = window.into_canvas()
-            .build()
-            .unwrap();
+    // Only one window should wait for vsync, or each would cost a frame
+    pub fn new(window: Window, integer_scale: bool, vsync: bool) -> Self {
+        let mut builder = window.into_canvas();
+        if vsync {
+            builder = builder.present_vsync();
+        }
+        let mut handle = builder.build().unwrap();
+        let texture_creator = handle.texture_creator();
 
-        let pixel_data = [[false; 64]; 32];
+        // SDL scales the logical size up to the window and letterboxes whatever is left over
+        handle.set_integer_scale(integer_scale).unwrap();
 
         Self {
             handle,
-            pixel_data,
+            texture_creator,
+            texture: None,
         }
     }
 
-    pub fn clear_screen(&mut self) {
-        self.handle.set_draw_color(Color::RGB(0, 0, 0));
-        self.handle.clear();
+    pub fn window(&self) -> &Window {
+        self.handle.window()
     }
 
-    pub fn invert_pixel(&mut self, x:usize, y:usize) -> bool {
-        if self.pixel_data[y][x] == true {
-            self.pixel_data[y][x] = false;
-            true
-        } else {
-            self.pixel_data[y][x] = true;
-            false
-        }
+    pub fn toggle_fullscreen(&mut self) {
+        let window = self.handle.window_mut();
+        let fullscreen = match window.fullscreen_state() {
+            FullscreenType::Off => FullscreenType::Desktop,
+            _ => FullscreenType::Off,
+        };
+        window.set_fullscreen(fullscreen).unwrap();
     }
 
-    pub fn update(&mut self) {
-        for y in 0..self.pixel_data.len() {
-            for x in 0..self.pixel_data[y].len() {
-                    
-                if self.pixel_data[y][x] {
-                    self.handle.set_draw_color(Color::WHITE);
-                } else {
-                    self.handle.set_draw_color(Color::BLACK);
-                }
-                
-                self.handle.fill_rect(Rect::new(
-                        (x as u32*PIXEL_SIZE) as i32, 
-                        (y as u32*PIXEL_SIZE) as i32,
-                        PIXEL_SIZE,
-                        PIXEL_SIZE
-                    )).unwrap();
+    pub fn toggle_integer_scale(&mut self) {
+        let integer_scale = !self.handle.integer_scale();
+        self.handle.set_integer_scale(integer_scale).unwrap();
+    }
+
+    // Uploads the framebuffer and copies it to the window, it is shown on the next `present`
+    pub fn draw_frame(&mut self, width: usize, height: usize, frame: &[Rgb]) {
+        let (width, height) = (width as u32, height as u32);
 
+        // Picks up resolution changes as well as the first frame
+        if self.handle.logical_size() != (width, height) {
+            self.handle.set_logical_size(width, height).unwrap();
+        }
+        let texture_size = self.texture.as_ref().map(|texture| {
+            let query = texture.query();
+            (query.width, query.height)
+        });
+        if texture_size != Some((width, height)) {
+            if let Some(texture) = self.texture.take() {
+                unsafe { texture.destroy(); }
             }
+            self.texture = Some(self.texture_creator
+                .create_texture_streaming(PixelFormatEnum::RGB24, width, height)
+                .unwrap());
         }
+
+        let texture = self.texture.as_mut().unwrap();
+        texture.with_lock(None, |pixels, pitch| {
+            for y in 0..height as usize {
+                for x in 0..width as usize {
+                    let (r, g, b) = frame[y * width as usize + x];
+                    let offset = y * pitch + x * 3;
+                    pixels[offset..offset+3].copy_from_slice(&[r, g, b]);
+                }
+            }
+        }).unwrap();
+
+        self.handle.set_draw_color(Color::BLACK);
+        self.handle.clear();
+        self.handle.copy(texture, None, None).unwrap();
+    }
+
+    pub fn present(&mut self) {
         self.handle.present();
     }
 }
@@ -1,174 +1,168 @@
 extern crate sdl2;
 
-use std::thread::Thread;
+use std::{collections::HashSet, path::PathBuf};
 
-use sdl2::{event::Event, EventPump, Sdl, keyboard::Keycode};
+use sdl2::{event::{Event, WindowEvent}, keyboard::Scancode, mouse::MouseButton, EventPump, Sdl};
+
+use crate::{browser::MenuKey, debugger::CursorMove, frontend::Hotkey};
+
+use super::keymap::KeyMap;
 
 pub struct EventHandler {
     event_pump: EventPump,
-    pub events: Vec<ChipKeyCode>,
+    keymap: KeyMap,
+    pub keys: [bool; 16],
+    pub held_hotkeys: HashSet<Hotkey>,
+    pub dropped_file: Option<PathBuf>,
+    // While set, keys drive the menu through `menu_keys` instead of the keypad
+    pub menu_open: bool,
+    pub menu_keys: Vec<MenuKey>,
+    // Input aimed at the debugger window, if it is open. Keys pressed there don't reach the keypad.
+    pub debugger_window: Option<u32>,
+    pub debugger_input: Vec<DebuggerInput>,
 }
 
-#[derive(PartialEq, Eq)]
-pub enum ChipKeyCode {
-    ONE,
-    TWO,
-    THREE,
-    FOUR,
-    FIVE,
-    SIX,
-    SEVEN,
-    EIGHT,
-    NINE,
-    ZERO,
-    A,
-    B,
-    C,
-    D,
-    E,
-    F,
+pub enum DebuggerInput {
+    Click(i32, i32),
+    Scroll(i32),
+    Cursor(CursorMove),
+    Text(String),
 }
 
 impl EventHandler {
     
-    pub fn new(sdl_context: &Sdl) -> Self {
+    pub fn new(sdl_context: &Sdl, keymap: KeyMap) -> Self {
         let event_pump = sdl_context.event_pump().unwrap();
 
         Self {
             event_pump,
-            events: Vec::new(),
+            keymap,
+            keys: [false; 16],
+            held_hotkeys: HashSet::new(),
+            dropped_file: None,
+            menu_open: false,
+            menu_keys: Vec::new(),
+            debugger_window: None,
+            debugger_input: Vec::new(),
         }
     }
 
-    pub fn is_pressed(&self, num: u8) -> bool {
-        for event in &self.events {
-            if num == match event {
-                ChipKeyCode::A => 0xA,
-                ChipKeyCode::B => 0xB,
-                ChipKeyCode::C => 0xC,
-                ChipKeyCode::D => 0xD,
-                ChipKeyCode::E => 0xE,
-                ChipKeyCode::F => 0xF,
-                ChipKeyCode::ONE => 0x1,
-                ChipKeyCode::TWO => 0x2,
-                ChipKeyCode::THREE => 0x3,
-                ChipKeyCode::FOUR => 0x4,
-                ChipKeyCode::FIVE => 0x5,
-                ChipKeyCode::SIX => 0x6,
-                ChipKeyCode::SEVEN => 0x7,
-                ChipKeyCode::EIGHT => 0x8,
-                ChipKeyCode::NINE => 0x9,
-                ChipKeyCode::ZERO => 0x0,
-            } {
-                return true;
-            }
-        }
-        false
-    }
+    // Returns the hotkeys pressed since the last update
+    pub fn update_events(&mut self) -> Vec<Hotkey> {
+        let mut hotkeys = Vec::new();
 
-    pub fn grab_key(&mut self) -> u8 {
-        match self.events.pop().unwrap() {
-            ChipKeyCode::A => 0xA,
-            ChipKeyCode::B => 0xB,
-            ChipKeyCode::C => 0xC,
-            ChipKeyCode::D => 0xD,
-            ChipKeyCode::E => 0xE,
-            ChipKeyCode::F => 0xF,
-            ChipKeyCode::ONE => 0x1,
-            ChipKeyCode::TWO => 0x2,
-            ChipKeyCode::THREE => 0x3,
-            ChipKeyCode::FOUR => 0x4,
-            ChipKeyCode::FIVE => 0x5,
-            ChipKeyCode::SIX => 0x6,
-            ChipKeyCode::SEVEN => 0x7,
-            ChipKeyCode::EIGHT => 0x8,
-            ChipKeyCode::NINE => 0x9,
-            ChipKeyCode::ZERO => 0x0,
-        }
-    }
+        for event in self.event_pump.poll_iter() {
+            match event {
+                // Menu keys repeat when held and the keypad is left alone
+                Event::KeyDown {
+                    scancode: Some(scancode),
+                    repeat,
+                    ..
+                } if self.menu_open => {
+                    if let Some(key) = menu_key(scancode) {
+                        self.menu_keys.push(key);
+                    } else if let (Some(hotkey), false) = (self.keymap.hotkey(scancode), repeat) {
+                        hotkeys.push(hotkey);
+                    }
+                },
+                Event::KeyDown {
+                    window_id,
+                    scancode: Some(scancode),
+                    ..
+                } if Some(window_id) == self.debugger_window && cursor_move(scancode).is_some() => {
+                    self.debugger_input.push(DebuggerInput::Cursor(cursor_move(scancode).unwrap()));
+                },
+                Event::KeyDown {
+                    window_id,
+                    scancode: Some(scancode),
+                    repeat: false,
+                    ..
+                } if Some(window_id) == self.debugger_window => {
+                    if let Some(hotkey) = self.keymap.hotkey(scancode) {
+                        self.held_hotkeys.insert(hotkey);
+                        hotkeys.push(hotkey);
+                    }
+                },
+                Event::TextInput { window_id, text, .. } if Some(window_id) == self.debugger_window => {
+                    self.debugger_input.push(DebuggerInput::Text(text));
+                },
+                Event::KeyDown {
+                    scancode: Some(scancode),
+                    repeat: false,
+                    ..
+                } => {
+                    if let Some(key) = self.keymap.keypad_key(scancode) {
+                        self.keys[key as usize] = true;
+                    } else if let Some(hotkey) = self.keymap.hotkey(scancode) {
+                        self.held_hotkeys.insert(hotkey);
+                        hotkeys.push(hotkey);
+                    }
+                },
+                Event::KeyUp {
+                    scancode: Some(scancode),
+                    ..
+                } => {
+                    if let Some(key) = self.keymap.keypad_key(scancode) {
+                        self.keys[key as usize] = false;
+                    } else if let Some(hotkey) = self.keymap.hotkey(scancode) {
+                        self.held_hotkeys.remove(&hotkey);
+                    }
+                },
 
-    pub fn update_events(&mut self) {
-        self.events.clear();
+                Event::MouseButtonDown {
+                    window_id,
+                    mouse_btn: MouseButton::Left,
+                    x,
+                    y,
+                    ..
+                } if Some(window_id) == self.debugger_window => self.debugger_input.push(DebuggerInput::Click(x, y)),
+                Event::MouseWheel { window_id, y, .. } if Some(window_id) == self.debugger_window => {
+                    // Wheel up means scrolling back
+                    self.debugger_input.push(DebuggerInput::Scroll(-y));
+                },
 
-        for event in self.event_pump.poll_iter() {
+                Event::DropFile { filename, .. } => self.dropped_file = Some(PathBuf::from(filename)),
 
-            let found_code: Option<ChipKeyCode> = match event {
-                Event::KeyDown {
-                    keycode: Some(Keycode::Num1),
-                    ..
-                } => Some(ChipKeyCode::ONE),
-                Event::KeyDown {
-                    keycode: Some(Keycode::Num2),
-                    ..
-                } => Some(ChipKeyCode::TWO),
-                Event::KeyDown {
-                    keycode: Some(Keycode::Num3),
-                    ..
-                } => Some(ChipKeyCode::THREE),
-                Event::KeyDown {
-                    keycode: Some(Keycode::Num4),
-                    ..
-                } => Some(ChipKeyCode::C),
-                Event::KeyDown {
-                    keycode: Some(Keycode::Q),
-                    ..
-                } => Some(ChipKeyCode::FOUR),
-                Event::KeyDown {
-                    keycode: Some(Keycode::W),
-                    ..
-                } => Some(ChipKeyCode::FIVE),
-                Event::KeyDown {
-                    keycode: Some(Keycode::E),
-                    ..
-                } => Some(ChipKeyCode::SIX),
-                Event::KeyDown {
-                    keycode: Some(Keycode::R),
+                // Closing the debugger only hides it, closing the game window quits even with the debugger open
+                Event::Window {
+                    window_id,
+                    win_event: WindowEvent::Close,
                     ..
-                } => Some(ChipKeyCode::D),
-                Event::KeyDown {
-                    keycode: Some(Keycode::A),
-                    ..
-                } => Some(ChipKeyCode::SEVEN),
-                Event::KeyDown {
-                    keycode: Some(Keycode::S),
-                    ..
-                } => Some(ChipKeyCode::EIGHT),
-                Event::KeyDown {
-                    keycode: Some(Keycode::D),
-                    ..
-                } => Some(ChipKeyCode::NINE),
-                Event::KeyDown {
-                    keycode: Some(Keycode::F),
-                    ..
-                } => Some(ChipKeyCode::E),
-                Event::KeyDown {
-                    keycode: Some(Keycode::Z),
-                    ..
-                } => Some(ChipKeyCode::A),
-                Event::KeyDown {
-                    keycode: Some(Keycode::X),
-                    ..
-                } => Some(ChipKeyCode::ZERO),
-                Event::KeyDown {
-                    keycode: Some(Keycode::C),
-                    ..
-                } => Some(ChipKeyCode::B),
-                Event::KeyDown {
-                    keycode: Some(Keycode::V),
-                    ..
-                } => Some(ChipKeyCode::F),
-
-                Event::Quit { .. } => {
-                    std::process::exit(0);
-                }
+                } => hotkeys.push(if Some(window_id) == self.debugger_window { Hotkey::Debugger } else { Hotkey::Quit }),
 
-                _ => {None}
-            }; 
+                Event::Quit { .. } => hotkeys.push(Hotkey::Quit),
 
-            if found_code.is_some() {
-                self.events.push(found_code.unwrap());
+                _ => {}
             }
         }
+
+        hotkeys
     }
 
 }
+
+fn menu_key(scancode: Scancode) -> Option<MenuKey> {
+    match scancode {
+        Scancode::Up => Some(MenuKey::Up),
+        Scancode::Down => Some(MenuKey::Down),
+        Scancode::PageUp => Some(MenuKey::PageUp),
+        Scancode::PageDown => Some(MenuKey::PageDown),
+        Scancode::Home => Some(MenuKey::Home),
+        Scancode::End => Some(MenuKey::End),
+        Scancode::Return | Scancode::KpEnter | Scancode::Space => Some(MenuKey::Select),
+        Scancode::Escape | Scancode::Backspace => Some(MenuKey::Back),
+        _ => None,
+    }
+}
+
+fn cursor_move(scancode: Scancode) -> Option<CursorMove> {
+    match scancode {
+        Scancode::Left => Some(CursorMove::Left),
+        Scancode::Right => Some(CursorMove::Right),
+        Scancode::Up => Some(CursorMove::Up),
+        Scancode::Down => Some(CursorMove::Down),
+        Scancode::PageUp => Some(CursorMove::PageUp),
+        Scancode::PageDown => Some(CursorMove::PageDown),
+        _ => None,
+    }
+}
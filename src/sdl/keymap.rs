@@ -0,0 +1,157 @@
+extern crate sdl2;
+
+use std::collections::HashMap;
+
+use sdl2::keyboard::Scancode;
+
+use crate::{config::Config, frontend::Hotkey};
+
+// Bindings are stored by scancode so they follow the physical key position
+// rather than whatever the host keyboard layout prints on the keycap.
+//
+// The `[keypad]` section of the config picks a preset and/or binds single keys:
+//
+//   [keypad]
+//   preset = qwerty
+//   Space = 5
+//
+// and `[hotkeys]` binds emulator functions:
+//
+//   [hotkeys]
+//   quit = Escape
+
+// Scancodes are named after the US layout, so the rows below are 1234/QWER/ASDF/ZXCV
+// on a QWERTY keyboard and 1234/AZER/QSDF/WXCV on an AZERTY one.
+const POSITIONAL: [(Scancode, u8); 16] = [
+    (Scancode::Num1, 0x1), (Scancode::Num2, 0x2), (Scancode::Num3, 0x3), (Scancode::Num4, 0xC),
+    (Scancode::Q, 0x4), (Scancode::W, 0x5), (Scancode::E, 0x6), (Scancode::R, 0xD),
+    (Scancode::A, 0x7), (Scancode::S, 0x8), (Scancode::D, 0x9), (Scancode::F, 0xE),
+    (Scancode::Z, 0xA), (Scancode::X, 0x0), (Scancode::C, 0xB), (Scancode::V, 0xF),
+];
+
+const NUMPAD: [(Scancode, u8); 16] = [
+    (Scancode::Kp0, 0x0), (Scancode::Kp1, 0x1), (Scancode::Kp2, 0x2), (Scancode::Kp3, 0x3),
+    (Scancode::Kp4, 0x4), (Scancode::Kp5, 0x5), (Scancode::Kp6, 0x6), (Scancode::Kp7, 0x7),
+    (Scancode::Kp8, 0x8), (Scancode::Kp9, 0x9), (Scancode::KpDivide, 0xA),
+    (Scancode::KpMultiply, 0xB), (Scancode::KpMinus, 0xC), (Scancode::KpPlus, 0xD),
+    (Scancode::KpEnter, 0xE), (Scancode::KpPeriod, 0xF),
+];
+
+// Every key is bound to the host key printed with the same hex digit
+const COSMAC_HEX: [(Scancode, u8); 16] = [
+    (Scancode::Num0, 0x0), (Scancode::Num1, 0x1), (Scancode::Num2, 0x2), (Scancode::Num3, 0x3),
+    (Scancode::Num4, 0x4), (Scancode::Num5, 0x5), (Scancode::Num6, 0x6), (Scancode::Num7, 0x7),
+    (Scancode::Num8, 0x8), (Scancode::Num9, 0x9), (Scancode::A, 0xA), (Scancode::B, 0xB),
+    (Scancode::C, 0xC), (Scancode::D, 0xD), (Scancode::E, 0xE), (Scancode::F, 0xF),
+];
+
+const DEFAULT_HOTKEYS: [(Scancode, Hotkey); 14] = [
+    (Scancode::Escape, Hotkey::Quit),
+    (Scancode::P, Hotkey::Pause),
+    (Scancode::F5, Hotkey::Reset),
+    (Scancode::N, Hotkey::FrameAdvance),
+    (Scancode::Tab, Hotkey::FastForward),
+    (Scancode::M, Hotkey::SlowMotion),
+    (Scancode::F1, Hotkey::Browser),
+    (Scancode::F3, Hotkey::Osd),
+    (Scancode::F6, Hotkey::Step),
+    (Scancode::F12, Hotkey::Debugger),
+    (Scancode::F11, Hotkey::Fullscreen),
+    (Scancode::F10, Hotkey::IntegerScale),
+    (Scancode::F9, Hotkey::CyclePalette),
+    (Scancode::F8, Hotkey::CycleFilter),
+];
+
+pub struct KeyMap {
+    keypad: HashMap<Scancode, u8>,
+    hotkeys: HashMap<Scancode, Hotkey>,
+}
+
+impl KeyMap {
+
+    pub fn preset(name: &str) -> Option<Self> {
+        let keypad: &[(Scancode, u8)] = match name.to_lowercase().as_str() {
+            "qwerty" | "azerty" => &POSITIONAL,
+            "numpad" => &NUMPAD,
+            "cosmac" | "hex" => &COSMAC_HEX,
+            _ => return None,
+        };
+
+        Some(Self {
+            keypad: keypad.iter().copied().collect(),
+            hotkeys: DEFAULT_HOTKEYS.iter().copied().collect(),
+        })
+    }
+
+    pub fn from_config(config: &Config) -> Result<Self, String> {
+        let mut keymap = Self::preset("qwerty").unwrap();
+        keymap.apply_config(config)?;
+        Ok(keymap)
+    }
+
+    pub fn apply_config(&mut self, config: &Config) -> Result<(), String> {
+        if let Some(preset) = config.get("keypad", "preset") {
+            let preset = Self::preset(preset)
+                .ok_or_else(|| format!("unknown keypad preset `{}`", preset))?;
+            self.keypad = preset.keypad;
+        }
+
+        for (key, value) in config.section("keypad") {
+            if key.eq_ignore_ascii_case("preset") {
+                continue;
+            }
+
+            let scancode = parse_scancode(key)?;
+            let chip_key = u8::from_str_radix(value.trim_start_matches("0x"), 16)
+                .ok()
+                .filter(|chip_key| *chip_key < 16)
+                .ok_or_else(|| format!("`{}` is not a keypad key (0-F)", value))?;
+            self.keypad.insert(scancode, chip_key);
+        }
+
+        for (key, value) in config.section("hotkeys") {
+            let hotkey = Hotkey::from_name(key)
+                .ok_or_else(|| format!("unknown hotkey `{}`", key))?;
+            let scancode = parse_scancode(value)?;
+            self.hotkeys.retain(|_, bound| *bound != hotkey);
+            self.hotkeys.insert(scancode, hotkey);
+        }
+
+        Ok(())
+    }
+
+    // Binds the game actions listed in the ROM database, e.g. `up` to the arrow key
+    pub fn bind_actions(&mut self, actions: &[(String, u8)]) {
+        for (action, chip_key) in actions {
+            let scancode = match action.as_str() {
+                "up" => Scancode::Up,
+                "down" => Scancode::Down,
+                "left" => Scancode::Left,
+                "right" => Scancode::Right,
+                "a" => Scancode::Space,
+                "b" => Scancode::LShift,
+                "player2Up" => Scancode::I,
+                "player2Down" => Scancode::K,
+                "player2Left" => Scancode::J,
+                "player2Right" => Scancode::L,
+                _ => continue,
+            };
+            if *chip_key < 16 {
+                self.keypad.insert(scancode, *chip_key);
+            }
+        }
+    }
+
+    pub fn keypad_key(&self, scancode: Scancode) -> Option<u8> {
+        self.keypad.get(&scancode).copied()
+    }
+
+    pub fn hotkey(&self, scancode: Scancode) -> Option<Hotkey> {
+        self.hotkeys.get(&scancode).copied()
+    }
+
+}
+
+fn parse_scancode(name: &str) -> Result<Scancode, String> {
+    Scancode::from_name(name).ok_or_else(|| format!("unknown key `{}`", name))
+}
@@ -0,0 +1,113 @@
+use crate::palette::Rgb;
+
+// A 5x7 pixel font for menus and overlays, drawn straight into RGB frames.
+// Lowercase letters are drawn as capitals and anything else outside ASCII 32-95 as `?`.
+
+// Glyphs sit on a 6x8 grid so neighbouring characters and lines have a pixel between them
+pub const CELL_WIDTH: usize = 6;
+pub const CELL_HEIGHT: usize = 8;
+
+// Rows from top to bottom, pixels in the top 5 bits like the CHIP-8 font
+const GLYPHS: [[u8; 7]; 64] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // space
+    [0x20, 0x20, 0x20, 0x20, 0x20, 0x00, 0x20], // !
+    [0x50, 0x50, 0x00, 0x00, 0x00, 0x00, 0x00], // "
+    [0x50, 0x50, 0xF8, 0x50, 0xF8, 0x50, 0x50], // #
+    [0x20, 0x78, 0xA0, 0x70, 0x28, 0xF0, 0x20], // $
+    [0xC0, 0xC8, 0x10, 0x20, 0x40, 0x98, 0x18], // %
+    [0x60, 0x90, 0xA0, 0x40, 0xA8, 0x90, 0x68], // &
+    [0x20, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00], // '
+    [0x10, 0x20, 0x40, 0x40, 0x40, 0x20, 0x10], // (
+    [0x40, 0x20, 0x10, 0x10, 0x10, 0x20, 0x40], // )
+    [0x00, 0x20, 0xA8, 0x70, 0xA8, 0x20, 0x00], // *
+    [0x00, 0x20, 0x20, 0xF8, 0x20, 0x20, 0x00], // +
+    [0x00, 0x00, 0x00, 0x00, 0x60, 0x20, 0x40], // ,
+    [0x00, 0x00, 0x00, 0xF8, 0x00, 0x00, 0x00], // -
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x60, 0x60], // .
+    [0x00, 0x08, 0x10, 0x20, 0x40, 0x80, 0x00], // /
+    [0x70, 0x88, 0x98, 0xA8, 0xC8, 0x88, 0x70], // 0
+    [0x20, 0x60, 0x20, 0x20, 0x20, 0x20, 0x70], // 1
+    [0x70, 0x88, 0x08, 0x10, 0x20, 0x40, 0xF8], // 2
+    [0xF8, 0x10, 0x20, 0x10, 0x08, 0x88, 0x70], // 3
+    [0x10, 0x30, 0x50, 0x90, 0xF8, 0x10, 0x10], // 4
+    [0xF8, 0x80, 0xF0, 0x08, 0x08, 0x88, 0x70], // 5
+    [0x30, 0x40, 0x80, 0xF0, 0x88, 0x88, 0x70], // 6
+    [0xF8, 0x08, 0x10, 0x20, 0x40, 0x40, 0x40], // 7
+    [0x70, 0x88, 0x88, 0x70, 0x88, 0x88, 0x70], // 8
+    [0x70, 0x88, 0x88, 0x78, 0x08, 0x10, 0x60], // 9
+    [0x00, 0x60, 0x60, 0x00, 0x60, 0x60, 0x00], // :
+    [0x00, 0x60, 0x60, 0x00, 0x60, 0x20, 0x40], // ;
+    [0x10, 0x20, 0x40, 0x80, 0x40, 0x20, 0x10], // <
+    [0x00, 0x00, 0xF8, 0x00, 0xF8, 0x00, 0x00], // =
+    [0x40, 0x20, 0x10, 0x08, 0x10, 0x20, 0x40], // >
+    [0x70, 0x88, 0x08, 0x10, 0x20, 0x00, 0x20], // ?
+    [0x70, 0x88, 0x08, 0x68, 0xA8, 0xA8, 0x70], // @
+    [0x70, 0x88, 0x88, 0xF8, 0x88, 0x88, 0x88], // A
+    [0xF0, 0x88, 0x88, 0xF0, 0x88, 0x88, 0xF0], // B
+    [0x70, 0x88, 0x80, 0x80, 0x80, 0x88, 0x70], // C
+    [0xE0, 0x90, 0x88, 0x88, 0x88, 0x90, 0xE0], // D
+    [0xF8, 0x80, 0x80, 0xF0, 0x80, 0x80, 0xF8], // E
+    [0xF8, 0x80, 0x80, 0xF0, 0x80, 0x80, 0x80], // F
+    [0x70, 0x88, 0x80, 0xB8, 0x88, 0x88, 0x78], // G
+    [0x88, 0x88, 0x88, 0xF8, 0x88, 0x88, 0x88], // H
+    [0x70, 0x20, 0x20, 0x20, 0x20, 0x20, 0x70], // I
+    [0x38, 0x10, 0x10, 0x10, 0x10, 0x90, 0x60], // J
+    [0x88, 0x90, 0xA0, 0xC0, 0xA0, 0x90, 0x88], // K
+    [0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0xF8], // L
+    [0x88, 0xD8, 0xA8, 0xA8, 0x88, 0x88, 0x88], // M
+    [0x88, 0x88, 0xC8, 0xA8, 0x98, 0x88, 0x88], // N
+    [0x70, 0x88, 0x88, 0x88, 0x88, 0x88, 0x70], // O
+    [0xF0, 0x88, 0x88, 0xF0, 0x80, 0x80, 0x80], // P
+    [0x70, 0x88, 0x88, 0x88, 0xA8, 0x90, 0x68], // Q
+    [0xF0, 0x88, 0x88, 0xF0, 0xA0, 0x90, 0x88], // R
+    [0x78, 0x80, 0x80, 0x70, 0x08, 0x08, 0xF0], // S
+    [0xF8, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20], // T
+    [0x88, 0x88, 0x88, 0x88, 0x88, 0x88, 0x70], // U
+    [0x88, 0x88, 0x88, 0x88, 0x88, 0x50, 0x20], // V
+    [0x88, 0x88, 0x88, 0xA8, 0xA8, 0xA8, 0x50], // W
+    [0x88, 0x88, 0x50, 0x20, 0x50, 0x88, 0x88], // X
+    [0x88, 0x88, 0x88, 0x50, 0x20, 0x20, 0x20], // Y
+    [0xF8, 0x08, 0x10, 0x20, 0x40, 0x80, 0xF8], // Z
+    [0x70, 0x40, 0x40, 0x40, 0x40, 0x40, 0x70], // [
+    [0x00, 0x80, 0x40, 0x20, 0x10, 0x08, 0x00], // \
+    [0x70, 0x10, 0x10, 0x10, 0x10, 0x10, 0x70], // ]
+    [0x20, 0x50, 0x88, 0x00, 0x00, 0x00, 0x00], // ^
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xF8], // _
+
+];
+
+fn glyph(c: char) -> &'static [u8; 7] {
+    let c = c.to_ascii_uppercase();
+    match c {
+        ' '..='_' => &GLYPHS[c as usize - ' ' as usize],
+        _ => &GLYPHS['?' as usize - ' ' as usize],
+    }
+}
+
+// Draws `text` with its top left corner at (x, y), anything past the frame edges is cut off
+pub fn draw(frame: &mut [Rgb], width: usize, x: usize, y: usize, text: &str, color: Rgb) {
+    let height = frame.len() / width;
+    for (index, c) in text.chars().enumerate() {
+        let left = x + index * CELL_WIDTH;
+        if left >= width {
+            break;
+        }
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for column in 0..5 {
+                let (pixel_x, pixel_y) = (left + column, y + row);
+                if bits & (0x80 >> column) != 0 && pixel_x < width && pixel_y < height {
+                    frame[pixel_y * width + pixel_x] = color;
+                }
+            }
+        }
+    }
+}
+
+pub fn fill_rect(frame: &mut [Rgb], width: usize, x: usize, y: usize, rect_width: usize, rect_height: usize, color: Rgb) {
+    let height = frame.len() / width;
+    for pixel_y in y..(y + rect_height).min(height) {
+        for pixel_x in x..(x + rect_width).min(width) {
+            frame[pixel_y * width + pixel_x] = color;
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use std::{fmt::Write as _, io::{self, Stdout, Write}, time::Duration};
+
+use crossterm::{
+    cursor,
+    event::{
+        self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers,
+        KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
+    execute,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+use crate::{emulation::{display::Display, Emulation}, frontend::{Frontend, Hotkey}, palette::Rgb};
+use keymap::KeyMap;
+
+pub mod graphics;
+pub mod keymap;
+
+// Most terminals only send repeated presses and never a release, so a key
+// counts as held until this many frames pass without it repeating
+const HOLD_FRAMES: u8 = 8;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RenderMode {
+    // Two pixels per cell in full colour using the upper half block
+    HalfBlock,
+    // Eight pixels per cell, lit pixels only, in the terminal's own colours
+    Braille,
+    // Real pixels through the Sixel or Kitty graphics protocols, without the register sidebar
+    Sixel,
+    Kitty,
+}
+
+pub struct TerminalFrontend {
+    stdout: Stdout,
+    mode: RenderMode,
+    // Size of a CHIP-8 pixel in the graphics modes
+    scale: usize,
+    // Frames left before a held key is released, see HOLD_FRAMES
+    held: [u8; 16],
+    // Same countdown for the fast-forward key
+    fast_forward: u8,
+    keymap: KeyMap,
+    release_events: bool,
+    output: String,
+}
+
+impl TerminalFrontend {
+
+    pub fn new(mode: RenderMode, scale: usize, keymap: KeyMap) -> io::Result<Self> {
+        let mut stdout = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(stdout, EnterAlternateScreen, cursor::Hide)?;
+
+        let release_events = terminal::supports_keyboard_enhancement().unwrap_or(false);
+        if release_events {
+            execute!(stdout, PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES))?;
+        }
+
+        Ok(Self {
+            stdout,
+            mode,
+            scale,
+            held: [0; 16],
+            fast_forward: 0,
+            keymap,
+            release_events,
+            output: String::new(),
+        })
+    }
+
+    fn handle_key(&mut self, key: KeyEvent, hotkeys: &mut Vec<Hotkey>) {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            hotkeys.push(Hotkey::Quit);
+            return;
+        }
+
+        if let Some(chip_key) = self.keymap.keypad_key(key.code) {
+            self.held[chip_key as usize] = self.hold_frames(key.kind);
+            return;
+        }
+        match self.keymap.hotkey(key.code) {
+            Some(Hotkey::FastForward) => self.fast_forward = self.hold_frames(key.kind),
+            Some(hotkey) if key.kind == KeyEventKind::Press => hotkeys.push(hotkey),
+            _ => {},
+        }
+    }
+
+    // Images are big, only send one when the picture actually changed
+    fn present_image(&mut self, image: Vec<u8>) {
+        let output = String::from_utf8(image).unwrap();
+        if output != self.output {
+            self.stdout.write_all(b"\x1b[H").unwrap();
+            self.stdout.write_all(output.as_bytes()).unwrap();
+            self.stdout.flush().unwrap();
+            self.output = output;
+        }
+    }
+
+    fn hold_frames(&self, kind: KeyEventKind) -> u8 {
+        match kind {
+            KeyEventKind::Release => 0,
+            // With release events the key stays down until it is let go
+            _ if self.release_events => u8::MAX,
+            _ => HOLD_FRAMES,
+        }
+    }
+
+}
+
+fn half_blocks(display: &Display, frame: &[Rgb]) -> Vec<String> {
+    let mut lines = Vec::new();
+    for y in (0..display.height).step_by(2) {
+        let mut line = String::new();
+        for x in 0..display.width {
+            let (tr, tg, tb) = frame[y * display.width + x];
+            let (br, bg, bb) = if y + 1 < display.height {
+                frame[(y + 1) * display.width + x]
+            } else {
+                (0, 0, 0)
+            };
+            write!(line, "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m\u{2580}", tr, tg, tb, br, bg, bb).unwrap();
+        }
+        line.push_str("\x1b[0m");
+        lines.push(line);
+    }
+    lines
+}
+
+fn braille(display: &Display) -> Vec<String> {
+    // Dot numbering of the braille block, indexed by [row][column]
+    const DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+    let mut lines = Vec::new();
+    for y in (0..display.height).step_by(4) {
+        let mut line = String::new();
+        for x in (0..display.width).step_by(2) {
+            let mut bits = 0;
+            for (row, dots) in DOTS.iter().enumerate() {
+                for (column, dot) in dots.iter().enumerate() {
+                    let (pixel_x, pixel_y) = (x + column, y + row);
+                    if pixel_x < display.width && pixel_y < display.height && display.get(pixel_x, pixel_y) != 0 {
+                        bits |= dot;
+                    }
+                }
+            }
+            line.push(char::from_u32(0x2800 + bits).unwrap());
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+fn register_sidebar(emulation: &Emulation) -> Vec<String> {
+    let chip = &emulation.chip8_data;
+    let mut lines: Vec<String> = (0..8)
+        .map(|i| format!("V{:X} {:02x}  V{:X} {:02x}", i, chip.var_registers[i], i + 8, chip.var_registers[i + 8]))
+        .collect();
+    lines.push(format!("PC {:03x}  I  {:03x}", chip.pc, chip.index));
+    lines.push(format!("DT {:02x}   ST {:02x}", chip.delay_timer, chip.sound_timer));
+    lines.push(format!("SP {:x}", chip.stack.len()));
+    lines
+}
+
+impl Frontend for TerminalFrontend {
+
+    fn poll_input(&mut self, keypad: &mut [bool; 16]) -> Vec<Hotkey> {
+        let mut hotkeys = Vec::new();
+
+        for held in self.held.iter_mut().chain([&mut self.fast_forward]) {
+            if *held != u8::MAX {
+                *held = held.saturating_sub(1);
+            }
+        }
+
+        while event::poll(Duration::ZERO).unwrap_or(false) {
+            match event::read() {
+                Ok(Event::Key(key)) => self.handle_key(key, &mut hotkeys),
+                Ok(_) => {},
+                Err(_) => break,
+            }
+        }
+
+        for (pressed, held) in keypad.iter_mut().zip(self.held) {
+            *pressed = held > 0;
+        }
+        hotkeys
+    }
+
+    fn held(&self, hotkey: Hotkey) -> bool {
+        hotkey == Hotkey::FastForward && self.fast_forward > 0
+    }
+
+    fn present(&mut self, emulation: &Emulation, frame: &[Rgb]) {
+        let display = &emulation.display;
+        let screen = match self.mode {
+            RenderMode::Sixel => return self.present_image(graphics::sixel(display.width, display.height, frame, self.scale)),
+            RenderMode::Kitty => return self.present_image(graphics::kitty(display.width, display.height, frame, self.scale)),
+            RenderMode::HalfBlock => half_blocks(display, frame),
+            RenderMode::Braille => braille(display),
+        };
+        let sidebar = register_sidebar(emulation);
+
+        let mut output = String::from("\x1b[H");
+        for row in 0..screen.len().max(sidebar.len()) {
+            let blank = " ".repeat(emulation.display.width / if self.mode == RenderMode::Braille { 2 } else { 1 });
+            output.push_str(screen.get(row).unwrap_or(&blank));
+            output.push_str("  ");
+            output.push_str(sidebar.get(row).map(|line| line.as_str()).unwrap_or(""));
+            output.push_str("\x1b[K\r\n");
+        }
+
+        // Nothing to do if the frame is unchanged, which is most frames
+        if output != self.output {
+            self.stdout.write_all(output.as_bytes()).unwrap();
+            self.stdout.flush().unwrap();
+            self.output = output;
+        }
+    }
+
+}
+
+impl Drop for TerminalFrontend {
+
+    fn drop(&mut self) {
+        if self.release_events {
+            let _ = execute!(self.stdout, PopKeyboardEnhancementFlags);
+        }
+        let _ = execute!(self.stdout, cursor::Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+
+}
@@ -0,0 +1,216 @@
+use std::fmt::Write as _;
+
+use crate::palette::Rgb;
+
+// Encoders for terminals that can show real pixels. Both take the filtered
+// frame, one colour per CHIP-8 pixel, and blow every pixel up to a
+// `scale` x `scale` square so the picture stays sharp.
+
+// Sixel only has 256 colour registers, past that colours are cut down to 3-3-2 bits
+const SIXEL_REGISTERS: usize = 256;
+
+// Kitty wants the payload split into chunks of at most 4096 base64 bytes
+const KITTY_CHUNK: usize = 4096;
+
+pub fn sixel(width: usize, height: usize, frame: &[Rgb], scale: usize) -> Vec<u8> {
+    let mut colors: Vec<Rgb> = Vec::new();
+    for color in frame {
+        if !colors.contains(color) {
+            colors.push(*color);
+        }
+    }
+    let quantize = colors.len() > SIXEL_REGISTERS;
+    if quantize {
+        colors = (0..=255u8).map(from_332).collect();
+    }
+    let register = |color: Rgb| -> usize {
+        if quantize {
+            to_332(color) as usize
+        } else {
+            colors.iter().position(|known| *known == color).unwrap()
+        }
+    };
+
+    let (image_width, image_height) = (width * scale, height * scale);
+    let pixel = |x: usize, y: usize| frame[(y / scale) * width + x / scale];
+
+    let mut out = String::new();
+    write!(out, "\x1bP0;1;0q\"1;1;{};{}", image_width, image_height).unwrap();
+    for (index, (r, g, b)) in colors.iter().enumerate() {
+        let percent = |channel: u8| channel as u32 * 100 / 255;
+        write!(out, "#{};2;{};{};{}", index, percent(*r), percent(*g), percent(*b)).unwrap();
+    }
+
+    // Each sixel character covers a column of six pixels, one band at a time
+    for band in (0..image_height).step_by(6) {
+        let mut used: Vec<usize> = Vec::new();
+        for y in band..(band + 6).min(image_height) {
+            for x in 0..image_width {
+                let index = register(pixel(x, y));
+                if !used.contains(&index) {
+                    used.push(index);
+                }
+            }
+        }
+
+        for (n, index) in used.iter().enumerate() {
+            if n > 0 {
+                out.push('$');
+            }
+            write!(out, "#{}", index).unwrap();
+
+            let mut run: Option<(char, usize)> = None;
+            for x in 0..image_width {
+                let mut bits = 0;
+                for row in 0..6 {
+                    let y = band + row;
+                    if y < image_height && register(pixel(x, y)) == *index {
+                        bits |= 1 << row;
+                    }
+                }
+                let sixel = char::from(63 + bits);
+
+                run = match run {
+                    Some((previous, count)) if previous == sixel => Some((previous, count + 1)),
+                    Some((previous, count)) => {
+                        push_run(&mut out, previous, count);
+                        Some((sixel, 1))
+                    },
+                    None => Some((sixel, 1)),
+                };
+            }
+            if let Some((previous, count)) = run {
+                push_run(&mut out, previous, count);
+            }
+        }
+        out.push('-');
+    }
+
+    out.push_str("\x1b\\");
+    out.into_bytes()
+}
+
+pub fn kitty(width: usize, height: usize, frame: &[Rgb], scale: usize) -> Vec<u8> {
+    let (image_width, image_height) = (width * scale, height * scale);
+
+    let mut rgb = Vec::with_capacity(image_width * image_height * 3);
+    for y in 0..image_height {
+        for x in 0..image_width {
+            let (r, g, b) = frame[(y / scale) * width + x / scale];
+            rgb.extend_from_slice(&[r, g, b]);
+        }
+    }
+    let payload = base64(&rgb);
+
+    // Image and placement ids stay the same so every frame replaces the last one
+    let mut out = String::new();
+    let chunks: Vec<&[u8]> = payload.as_bytes().chunks(KITTY_CHUNK).collect();
+    for (n, chunk) in chunks.iter().enumerate() {
+        let more = if n + 1 < chunks.len() { 1 } else { 0 };
+        if n == 0 {
+            write!(out, "\x1b_Ga=T,f=24,s={},v={},i=1,p=1,q=2,C=1,m={};", image_width, image_height, more).unwrap();
+        } else {
+            write!(out, "\x1b_Gm={};", more).unwrap();
+        }
+        out.push_str(std::str::from_utf8(chunk).unwrap());
+        out.push_str("\x1b\\");
+    }
+
+    out.into_bytes()
+}
+
+fn push_run(out: &mut String, sixel: char, count: usize) {
+    if count > 3 {
+        write!(out, "!{}{}", count, sixel).unwrap();
+    } else {
+        for _ in 0..count {
+            out.push(sixel);
+        }
+    }
+}
+
+fn to_332((r, g, b): Rgb) -> u8 {
+    (r & 0xE0) | ((g & 0xE0) >> 3) | (b >> 6)
+}
+
+fn from_332(bits: u8) -> Rgb {
+    (bits & 0xE0, (bits & 0x1C) << 3, (bits & 0x03) << 6)
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let value = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+
+        for n in 0..4 {
+            if n <= chunk.len() {
+                out.push(ALPHABET[(value >> (18 - n * 6) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: Rgb = (255, 255, 255);
+    const BLACK: Rgb = (0, 0, 0);
+
+    #[test]
+    fn sixel_one_register_per_colour() {
+        let image = sixel(2, 1, &[WHITE, BLACK], 1);
+        assert_eq!(
+            String::from_utf8(image).unwrap(),
+            "\x1bP0;1;0q\"1;1;2;1#0;2;100;100;100#1;2;0;0;0#0@?$#1?@-\x1b\\"
+        );
+    }
+
+    #[test]
+    fn sixel_scales_and_run_length_encodes() {
+        let image = sixel(1, 1, &[WHITE], 4);
+        assert_eq!(String::from_utf8(image).unwrap(), "\x1bP0;1;0q\"1;1;4;4#0;2;100;100;100#0!4N-\x1b\\");
+    }
+
+    #[test]
+    fn sixel_quantizes_past_256_colours() {
+        let frame: Vec<Rgb> = (0..300).map(|n| ((n % 256) as u8, (n / 256) as u8, 0)).collect();
+        let image = String::from_utf8(sixel(300, 1, &frame, 1)).unwrap();
+        assert!(image.contains("#255;2;87;87;75"));
+        assert!(!image.contains("#256;"));
+    }
+
+    #[test]
+    fn kitty_single_chunk() {
+        let image = kitty(1, 1, &[(255, 0, 0)], 1);
+        assert_eq!(
+            String::from_utf8(image).unwrap(),
+            "\x1b_Ga=T,f=24,s=1,v=1,i=1,p=1,q=2,C=1,m=0;/wAA\x1b\\"
+        );
+    }
+
+    #[test]
+    fn kitty_splits_large_payloads() {
+        // 64 x 32 pixels of RGB is 8192 bytes of base64, two full chunks
+        let image = String::from_utf8(kitty(64, 32, &[BLACK; 64 * 32], 1)).unwrap();
+        let chunks: Vec<&str> = image.split("\x1b\\").filter(|chunk| !chunk.is_empty()).collect();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0], format!("\x1b_Ga=T,f=24,s=64,v=32,i=1,p=1,q=2,C=1,m=1;{}", "A".repeat(KITTY_CHUNK)));
+        assert_eq!(chunks[1], format!("\x1b_Gm=0;{}", "A".repeat(KITTY_CHUNK)));
+    }
+
+    #[test]
+    fn base64_padding() {
+        assert_eq!(base64(b"Man"), "TWFu");
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"M"), "TQ==");
+        assert_eq!(base64(b""), "");
+    }
+}
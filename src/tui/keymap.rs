@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+
+use crossterm::event::KeyCode;
+
+use crate::{config::Config, frontend::Hotkey};
+
+// The terminal side of `sdl::keymap`, read from the same `[keypad]` and `[hotkeys]`
+// sections of the config. Terminals send characters rather than key positions, so each
+// layout gets its own preset. Keys a terminal can't tell apart from others, like
+// `Keypad 5` or `Left Shift`, are left to the SDL frontend.
+
+const QWERTY: [(KeyCode, u8); 16] = [
+    (KeyCode::Char('1'), 0x1), (KeyCode::Char('2'), 0x2), (KeyCode::Char('3'), 0x3), (KeyCode::Char('4'), 0xC),
+    (KeyCode::Char('q'), 0x4), (KeyCode::Char('w'), 0x5), (KeyCode::Char('e'), 0x6), (KeyCode::Char('r'), 0xD),
+    (KeyCode::Char('a'), 0x7), (KeyCode::Char('s'), 0x8), (KeyCode::Char('d'), 0x9), (KeyCode::Char('f'), 0xE),
+    (KeyCode::Char('z'), 0xA), (KeyCode::Char('x'), 0x0), (KeyCode::Char('c'), 0xB), (KeyCode::Char('v'), 0xF),
+];
+
+const AZERTY: [(KeyCode, u8); 16] = [
+    (KeyCode::Char('1'), 0x1), (KeyCode::Char('2'), 0x2), (KeyCode::Char('3'), 0x3), (KeyCode::Char('4'), 0xC),
+    (KeyCode::Char('a'), 0x4), (KeyCode::Char('z'), 0x5), (KeyCode::Char('e'), 0x6), (KeyCode::Char('r'), 0xD),
+    (KeyCode::Char('q'), 0x7), (KeyCode::Char('s'), 0x8), (KeyCode::Char('d'), 0x9), (KeyCode::Char('f'), 0xE),
+    (KeyCode::Char('w'), 0xA), (KeyCode::Char('x'), 0x0), (KeyCode::Char('c'), 0xB), (KeyCode::Char('v'), 0xF),
+];
+
+// The numpad sends the same digits as the top row
+const NUMPAD: [(KeyCode, u8); 16] = [
+    (KeyCode::Char('0'), 0x0), (KeyCode::Char('1'), 0x1), (KeyCode::Char('2'), 0x2), (KeyCode::Char('3'), 0x3),
+    (KeyCode::Char('4'), 0x4), (KeyCode::Char('5'), 0x5), (KeyCode::Char('6'), 0x6), (KeyCode::Char('7'), 0x7),
+    (KeyCode::Char('8'), 0x8), (KeyCode::Char('9'), 0x9), (KeyCode::Char('/'), 0xA),
+    (KeyCode::Char('*'), 0xB), (KeyCode::Char('-'), 0xC), (KeyCode::Char('+'), 0xD),
+    (KeyCode::Enter, 0xE), (KeyCode::Char('.'), 0xF),
+];
+
+const COSMAC_HEX: [(KeyCode, u8); 16] = [
+    (KeyCode::Char('0'), 0x0), (KeyCode::Char('1'), 0x1), (KeyCode::Char('2'), 0x2), (KeyCode::Char('3'), 0x3),
+    (KeyCode::Char('4'), 0x4), (KeyCode::Char('5'), 0x5), (KeyCode::Char('6'), 0x6), (KeyCode::Char('7'), 0x7),
+    (KeyCode::Char('8'), 0x8), (KeyCode::Char('9'), 0x9), (KeyCode::Char('a'), 0xA), (KeyCode::Char('b'), 0xB),
+    (KeyCode::Char('c'), 0xC), (KeyCode::Char('d'), 0xD), (KeyCode::Char('e'), 0xE), (KeyCode::Char('f'), 0xF),
+];
+
+// Only what the terminal frontend can do, there is no browser, debugger or window
+const DEFAULT_HOTKEYS: [(KeyCode, Hotkey); 8] = [
+    (KeyCode::Esc, Hotkey::Quit),
+    (KeyCode::Char('p'), Hotkey::Pause),
+    (KeyCode::F(5), Hotkey::Reset),
+    (KeyCode::Char('n'), Hotkey::FrameAdvance),
+    (KeyCode::Tab, Hotkey::FastForward),
+    (KeyCode::Char('m'), Hotkey::SlowMotion),
+    (KeyCode::F(9), Hotkey::CyclePalette),
+    (KeyCode::F(8), Hotkey::CycleFilter),
+];
+
+pub struct KeyMap {
+    keypad: HashMap<KeyCode, u8>,
+    hotkeys: HashMap<KeyCode, Hotkey>,
+}
+
+impl KeyMap {
+
+    pub fn preset(name: &str) -> Option<Self> {
+        let keypad: &[(KeyCode, u8)] = match name.to_lowercase().as_str() {
+            "qwerty" => &QWERTY,
+            "azerty" => &AZERTY,
+            "numpad" => &NUMPAD,
+            "cosmac" | "hex" => &COSMAC_HEX,
+            _ => return None,
+        };
+
+        Some(Self {
+            keypad: keypad.iter().copied().collect(),
+            hotkeys: DEFAULT_HOTKEYS.iter().copied().collect(),
+        })
+    }
+
+    pub fn from_config(config: &Config) -> Result<Self, String> {
+        let mut keymap = Self::preset("qwerty").unwrap();
+        keymap.apply_config(config)?;
+        Ok(keymap)
+    }
+
+    pub fn apply_config(&mut self, config: &Config) -> Result<(), String> {
+        if let Some(preset) = config.get("keypad", "preset") {
+            let preset = Self::preset(preset)
+                .ok_or_else(|| format!("unknown keypad preset `{}`", preset))?;
+            self.keypad = preset.keypad;
+        }
+
+        for (key, value) in config.section("keypad") {
+            if key.eq_ignore_ascii_case("preset") {
+                continue;
+            }
+
+            let chip_key = u8::from_str_radix(value.trim_start_matches("0x"), 16)
+                .ok()
+                .filter(|chip_key| *chip_key < 16)
+                .ok_or_else(|| format!("`{}` is not a keypad key (0-F)", value))?;
+            if let Some(code) = parse_key(key) {
+                self.keypad.insert(code, chip_key);
+            }
+        }
+
+        for (key, value) in config.section("hotkeys") {
+            let hotkey = Hotkey::from_name(key)
+                .ok_or_else(|| format!("unknown hotkey `{}`", key))?;
+            if let Some(code) = parse_key(value) {
+                self.hotkeys.retain(|_, bound| *bound != hotkey);
+                self.hotkeys.insert(code, hotkey);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Binds the game actions listed in the ROM database, e.g. `up` to the arrow key
+    pub fn bind_actions(&mut self, actions: &[(String, u8)]) {
+        for (action, chip_key) in actions {
+            let code = match action.as_str() {
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "a" => KeyCode::Char(' '),
+                "player2Up" => KeyCode::Char('i'),
+                "player2Down" => KeyCode::Char('k'),
+                "player2Left" => KeyCode::Char('j'),
+                "player2Right" => KeyCode::Char('l'),
+                _ => continue,
+            };
+            if *chip_key < 16 {
+                self.keypad.insert(code, *chip_key);
+            }
+        }
+    }
+
+    pub fn keypad_key(&self, code: KeyCode) -> Option<u8> {
+        self.keypad.get(&lowercase(code)).copied()
+    }
+
+    pub fn hotkey(&self, code: KeyCode) -> Option<Hotkey> {
+        self.hotkeys.get(&lowercase(code)).copied()
+    }
+
+}
+
+// Shift doesn't change which key was pressed
+fn lowercase(code: KeyCode) -> KeyCode {
+    match code {
+        KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+        _ => code,
+    }
+}
+
+// Takes the SDL key names the config is written with, None for ones only SDL can see
+fn parse_key(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c.to_ascii_lowercase()));
+    }
+
+    let code = match name.to_lowercase().as_str() {
+        "space" => KeyCode::Char(' '),
+        "escape" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "return" | "enter" => KeyCode::Enter,
+        "backspace" => KeyCode::Backspace,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "insert" => KeyCode::Insert,
+        "delete" => KeyCode::Delete,
+        function => KeyCode::F(function.strip_prefix('f')?.parse().ok().filter(|n| (1..=12).contains(n))?),
+    };
+    Some(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(config: &str) -> Result<KeyMap, String> {
+        KeyMap::from_config(&Config::parse(config).unwrap())
+    }
+
+    #[test]
+    fn qwerty_by_default() {
+        let keymap = load("").unwrap();
+        assert_eq!(keymap.keypad_key(KeyCode::Char('x')), Some(0x0));
+        assert_eq!(keymap.keypad_key(KeyCode::Char('V')), Some(0xF));
+        assert_eq!(keymap.hotkey(KeyCode::Esc), Some(Hotkey::Quit));
+    }
+
+    #[test]
+    fn presets_and_single_keys() {
+        let keymap = load("[keypad]\npreset = azerty\nSpace = 5\nKeypad 5 = 5\n").unwrap();
+        assert_eq!(keymap.keypad_key(KeyCode::Char('z')), Some(0x5));
+        assert_eq!(keymap.keypad_key(KeyCode::Char('w')), Some(0xA));
+        assert_eq!(keymap.keypad_key(KeyCode::Char(' ')), Some(0x5));
+
+        assert!(load("[keypad]\npreset = dvorak\n").is_err());
+        assert!(load("[keypad]\nSpace = 16\n").is_err());
+    }
+
+    #[test]
+    fn hotkeys_move() {
+        let keymap = load("[hotkeys]\npause = F2\nquit = Left Ctrl\n").unwrap();
+        assert_eq!(keymap.hotkey(KeyCode::F(2)), Some(Hotkey::Pause));
+        assert_eq!(keymap.hotkey(KeyCode::Char('p')), None);
+        // Not something a terminal sends, the old binding stays
+        assert_eq!(keymap.hotkey(KeyCode::Esc), Some(Hotkey::Quit));
+
+        assert!(load("[hotkeys]\nfly = F2\n").is_err());
+    }
+}
@@ -0,0 +1,83 @@
+// Every 8XYN instruction against a reference model, for all 65536 pairs of VX and VY under each
+// platform's quirks
+
+use chip_8_emulator::emulation::{
+    quirks::{Platform, Quirks},
+    Emulation,
+};
+
+const PLATFORMS: [Platform; 4] = [Platform::Chip8, Platform::ModernChip8, Platform::SuperChip, Platform::XoChip];
+const OPERATIONS: [u8; 9] = [0x0, 0x1, 0x2, 0x3, 0x4, 0x5, 0x6, 0x7, 0xE];
+// What VF holds before the instruction, to tell an untouched flag from one that was written
+const UNTOUCHED: u8 = 0xAA;
+
+// The new VX, and VF if the instruction sets it
+fn reference(operation: u8, vx: u8, vy: u8, quirks: Quirks) -> (u8, Option<u8>) {
+    let logic_flag = if quirks.vf_reset { Some(0) } else { None };
+    let shifted = if quirks.shift_vx { vx } else { vy };
+    match operation {
+        0x0 => (vy, None),
+        0x1 => (vx | vy, logic_flag),
+        0x2 => (vx & vy, logic_flag),
+        0x3 => (vx ^ vy, logic_flag),
+        0x4 => ((vx as u16 + vy as u16) as u8, Some((vx as u16 + vy as u16 > 0xFF) as u8)),
+        0x5 => ((vx as i16 - vy as i16) as u8, Some((vx >= vy) as u8)),
+        0x6 => (shifted >> 1, Some(shifted & 1)),
+        0x7 => ((vy as i16 - vx as i16) as u8, Some((vy >= vx) as u8)),
+        0xE => (((shifted as u16) << 1) as u8, Some(shifted >> 7)),
+        _ => unreachable!(),
+    }
+}
+
+// Runs 8XYN once with the given register values and returns the registers after it
+fn execute(emulation: &mut Emulation, x: usize, y: usize, operation: u8, vx: u8, vy: u8) -> [u8; 16] {
+    let chip = &mut emulation.chip8_data;
+    chip.pc = 0x200;
+    chip.memory[0x200] = 0x80 | x as u8;
+    chip.memory[0x201] = (y as u8) << 4 | operation;
+    chip.var_registers = [0; 16];
+    chip.var_registers[0xF] = UNTOUCHED;
+    chip.var_registers[x] = vx;
+    chip.var_registers[y] = vy;
+    emulation.step();
+    emulation.chip8_data.var_registers
+}
+
+#[test]
+fn matches_reference() {
+    for platform in PLATFORMS {
+        let mut emulation = Emulation::new(platform, 0);
+        for operation in OPERATIONS {
+            for vx in 0..=255 {
+                for vy in 0..=255 {
+                    let (result, flag) = reference(operation, vx, vy, platform.quirks());
+                    let registers = execute(&mut emulation, 0x1, 0x2, operation, vx, vy);
+                    assert_eq!(
+                        (registers[0x1], registers[0x2], registers[0xF]), (result, vy, flag.unwrap_or(UNTOUCHED)),
+                        "VX, VY and VF of {:?} 8XY{:X} VX={:#04x} VY={:#04x}", platform, operation, vx, vy
+                    );
+                }
+            }
+        }
+    }
+}
+
+// With VF as VX the flag is written after the result, so it is the flag that's left
+#[test]
+fn flag_written_last() {
+    for platform in PLATFORMS {
+        let mut emulation = Emulation::new(platform, 0);
+        for operation in OPERATIONS {
+            for vx in 0..=255 {
+                for vy in 0..=255 {
+                    let (result, flag) = reference(operation, vx, vy, platform.quirks());
+                    let registers = execute(&mut emulation, 0xF, 0x2, operation, vx, vy);
+                    assert_eq!(
+                        registers[0xF], flag.unwrap_or(result),
+                        "VF of {:?} 8FY{:X} VF={:#04x} VY={:#04x}", platform, operation, vx, vy
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,98 @@
+// Cheat file parsing and RAM search narrowing
+
+use chip_8_emulator::emulation::{
+    cheats::{self, Cheat, Comparison, RamSearch},
+    quirks::Platform,
+    Emulation,
+};
+
+#[test]
+fn round_trip() {
+    let cheats = vec![
+        Cheat { address: 0x000, value: 0x00 },
+        Cheat { address: 0x2a4, value: 0x09 },
+        Cheat { address: 0xfff, value: 0xff },
+    ];
+    let text = cheats::to_text(&cheats);
+    assert_eq!(text, "000 = 00\n2a4 = 09\nfff = ff\n");
+    assert_eq!(cheats::parse(&text).unwrap(), cheats);
+}
+
+#[test]
+fn comments_and_blank_lines() {
+    let text = "# lives\n\n  2A4=9   # infinite\n\t3 = FF\n#300 = 01\n";
+    assert_eq!(
+        cheats::parse(text).unwrap(),
+        [Cheat { address: 0x2a4, value: 0x09 }, Cheat { address: 0x003, value: 0xff }]
+    );
+}
+
+#[test]
+fn invalid_lines() {
+    for (text, line) in [
+        ("2a4 = 09\n2a5\n", 2),
+        ("1000 = 01\n", 1),
+        ("2a4 = 100\n", 1),
+        ("\n# ok\nzz = 01\n", 3),
+        ("2a4 = \n", 1),
+    ] {
+        assert_eq!(cheats::parse(text).unwrap_err(), format!("line {}: expected `address = value` in hex", line));
+    }
+}
+
+// Candidates left after one search step from `before` to `after`
+fn narrow(before: &[u8], after: &[u8], comparison: Comparison) -> Vec<u16> {
+    let mut search = RamSearch::new(before);
+    search.filter(after, comparison);
+    search.candidates
+}
+
+#[test]
+fn search_comparisons() {
+    let before = [5, 5, 5, 5];
+    let after = [6, 4, 5, 5];
+    assert_eq!(narrow(&before, &after, Comparison::Equal), [2, 3]);
+    assert_eq!(narrow(&before, &after, Comparison::Changed), [0, 1]);
+    assert_eq!(narrow(&before, &after, Comparison::Increased), [0]);
+    assert_eq!(narrow(&before, &after, Comparison::Decreased), [1]);
+}
+
+// Each step compares against the memory of the step before, not the first snapshot
+#[test]
+fn search_steps() {
+    let mut memory = [5, 5, 5, 5];
+    let mut search = RamSearch::new(&memory);
+    assert_eq!(search.candidates, [0, 1, 2, 3]);
+
+    memory = [4, 4, 5, 6];
+    search.filter(&memory, Comparison::Decreased);
+    assert_eq!(search.candidates, [0, 1]);
+    assert_eq!(search.previous(0), 4);
+
+    memory = [4, 3, 5, 6];
+    search.filter(&memory, Comparison::Decreased);
+    assert_eq!(search.candidates, [1]);
+
+    search.filter(&memory, Comparison::Equal);
+    assert_eq!(search.candidates, [1]);
+    memory[1] = 9;
+    search.filter(&memory, Comparison::Increased);
+    assert_eq!(search.candidates, [1]);
+    search.filter(&memory, Comparison::Changed);
+    assert!(search.candidates.is_empty());
+}
+
+#[test]
+fn cheats_stay_inside_memory() {
+    let mut emulation = Emulation::new(Platform::Chip8, 0);
+    emulation.set_cheat(0x1234, 0xAB);
+    assert_eq!(emulation.cheats, [Cheat { address: 0x234, value: 0xAB }]);
+
+    emulation.cheats.push(Cheat { address: 0xFFFF, value: 0xCD });
+    emulation.apply_cheats();
+    assert_eq!(emulation.chip8_data.memory[0x234], 0xAB);
+    assert_eq!(emulation.chip8_data.memory[0xFFF], 0xCD);
+
+    emulation.remove_cheat(0x1234);
+    assert_eq!(emulation.cheats, [Cheat { address: 0xFFFF, value: 0xCD }]);
+}
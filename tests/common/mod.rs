@@ -0,0 +1,32 @@
+// Helpers for the tests that talk to an emulator process over TCP. Each test file
+// only uses some of them.
+#![allow(dead_code)]
+
+use std::{
+    net::{TcpListener, TcpStream},
+    thread,
+    time::{Duration, Instant},
+};
+
+pub const EMULATOR: &str = env!("CARGO_BIN_EXE_chip-8_emulator");
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+// A port nothing is listening on, the OS picks it and it is released right away
+pub fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+// Retries until the emulator that was just started is listening
+pub fn connect(port: u16) -> TcpStream {
+    let start = Instant::now();
+    let stream = loop {
+        match TcpStream::connect(("127.0.0.1", port)) {
+            Ok(stream) => break stream,
+            Err(err) if start.elapsed() > TIMEOUT => panic!("could not connect: {}", err),
+            Err(_) => thread::sleep(Duration::from_millis(50)),
+        }
+    };
+    stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+    stream
+}
@@ -0,0 +1,110 @@
+// Drives a paused headless run through the JSON control socket
+
+mod common;
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpStream,
+    process::{Child, Command, Stdio},
+};
+
+use serde_json::{json, Value};
+
+use common::{connect, free_port, EMULATOR};
+
+struct Client {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Client {
+
+    fn connect(port: u16) -> Self {
+        let stream = connect(port);
+        Self {
+            reader: BufReader::new(stream.try_clone().unwrap()),
+            writer: stream,
+        }
+    }
+
+    fn send_line(&mut self, line: &str) -> Value {
+        writeln!(self.writer, "{}", line).unwrap();
+        let mut reply = String::new();
+        self.reader.read_line(&mut reply).unwrap();
+        serde_json::from_str(&reply).unwrap()
+    }
+
+    // Fails the test unless the command succeeds
+    fn send(&mut self, command: Value) -> Value {
+        let reply = self.send_line(&command.to_string());
+        assert_eq!(reply["ok"], true, "{} failed: {}", command, reply);
+        reply
+    }
+
+}
+
+// A paused headless run of `rom` with the control socket open
+fn start(rom: &str) -> (Child, Client) {
+    let port = free_port();
+    let emulator = Command::new(EMULATOR)
+        .args([rom, "--headless", "1000000", "--paused", "--control", &port.to_string()])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    (emulator, Client::connect(port))
+}
+
+#[test]
+fn commands() {
+    let (emulator, mut control) = start("roms/IBM_Logo.ch8");
+
+    let registers = control.send(json!({ "cmd": "registers" }));
+    assert_eq!(registers["pc"], 0x200);
+    assert_eq!(control.send(json!({ "cmd": "memory", "address": 0x200, "length": 4 }))["data"], "00e0a22a");
+
+    let blank = control.send(json!({ "cmd": "framebuffer" }));
+    assert_eq!(blank["width"], 64);
+    assert!(blank["pixels"].as_array().unwrap().iter().all(|pixel| *pixel == 0));
+
+    let state = control.send(json!({ "cmd": "save_state" }))["state"].clone();
+    control.send(json!({ "cmd": "step", "frames": 10 }));
+    let logo = control.send(json!({ "cmd": "framebuffer" }));
+    assert!(logo["pixels"].as_array().unwrap().iter().any(|pixel| *pixel == 1));
+
+    control.send(json!({ "cmd": "load_state", "state": state }));
+    assert_eq!(control.send(json!({ "cmd": "registers" }))["pc"], 0x200);
+    control.send(json!({ "cmd": "press", "key": 5 }));
+    control.send(json!({ "cmd": "release", "key": 5 }));
+
+    assert_eq!(control.send_line(r#"{"cmd": "press", "key": 16}"#)["ok"], false);
+    assert_eq!(control.send_line(r#"{"cmd": "fly"}"#)["ok"], false);
+    assert_eq!(control.send_line("not json")["ok"], false);
+    assert_eq!(control.send_line(r#"{"cmd": "load_rom", "path": "roms/missing.ch8"}"#)["ok"], false);
+
+    control.send(json!({ "cmd": "load_rom", "path": "roms/tetris.rom" }));
+    control.send(json!({ "cmd": "step", "frames": 5 }));
+    control.send(json!({ "cmd": "quit" }));
+
+    let output = emulator.wait_with_output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains('#'));
+}
+
+#[test]
+fn held_keys_reach_the_program() {
+    // V0 = 1 once key 5 is down:  V5 = 5, loop: SKP V5, JP loop, V0 = 1, halt: JP halt
+    let rom = std::env::temp_dir().join(format!("held_key_{}.ch8", std::process::id()));
+    std::fs::write(&rom, [0x65, 0x05, 0xE5, 0x9E, 0x12, 0x02, 0x60, 0x01, 0x12, 0x08]).unwrap();
+    let (emulator, mut control) = start(rom.to_str().unwrap());
+
+    control.send(json!({ "cmd": "step", "frames": 2 }));
+    assert_eq!(control.send(json!({ "cmd": "registers" }))["v"][0], 0);
+    control.send(json!({ "cmd": "press", "key": 5 }));
+    control.send(json!({ "cmd": "step" }));
+    assert_eq!(control.send(json!({ "cmd": "registers" }))["v"][0], 1);
+
+    control.send(json!({ "cmd": "quit" }));
+    assert!(emulator.wait_with_output().unwrap().status.success());
+    std::fs::remove_file(rom).unwrap();
+}
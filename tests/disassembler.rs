@@ -0,0 +1,86 @@
+// Mnemonics for known opcodes, and DW for everything the interpreter doesn't run
+
+use chip_8_emulator::emulation::{
+    disassembler::disassemble,
+    instruction::Instruction,
+    quirks::Platform,
+    Emulation,
+};
+
+#[test]
+fn instructions() {
+    let cases = [
+        (0x00E0, "CLS"),
+        (0x00EE, "RET"),
+        (0x00FE, "LOW"),
+        (0x00FF, "HIGH"),
+        (0x1234, "JP 234"),
+        (0x2ABC, "CALL abc"),
+        (0x3A2F, "SE Va, 2f"),
+        (0x4B00, "SNE Vb, 00"),
+        (0x5120, "SE V1, V2"),
+        (0x6C7F, "LD Vc, 7f"),
+        (0x7D01, "ADD Vd, 01"),
+        (0x8120, "LD V1, V2"),
+        (0x8121, "OR V1, V2"),
+        (0x8122, "AND V1, V2"),
+        (0x8123, "XOR V1, V2"),
+        (0x8124, "ADD V1, V2"),
+        (0x8125, "SUB V1, V2"),
+        (0x8126, "SHR V1, V2"),
+        (0x8127, "SUBN V1, V2"),
+        (0x812E, "SHL V1, V2"),
+        (0x9EF0, "SNE Ve, Vf"),
+        (0xA22A, "LD I, 22a"),
+        (0xB300, "JP V0, 300"),
+        (0xC5FF, "RND V5, ff"),
+        (0xD01F, "DRW V0, V1, f"),
+        (0xE39E, "SKP V3"),
+        (0xE3A1, "SKNP V3"),
+        (0xF301, "PLANE 3"),
+        (0xF002, "AUDIO"),
+        (0xF407, "LD V4, DT"),
+        (0xF40A, "LD V4, K"),
+        (0xF415, "LD DT, V4"),
+        (0xF418, "LD ST, V4"),
+        (0xF41E, "ADD I, V4"),
+        (0xF429, "LD F, V4"),
+        (0xF433, "LD B, V4"),
+        (0xF43A, "PITCH V4"),
+        (0xF455, "LD [I], V4"),
+        (0xF465, "LD V4, [I]"),
+    ];
+    let quirks = Platform::Chip8.quirks();
+    for (opcode, mnemonic) in cases {
+        assert_eq!(disassemble(opcode, &quirks), mnemonic, "{:04x}", opcode);
+    }
+}
+
+#[test]
+fn data_words() {
+    // Machine code calls, unused N digits and unknown second bytes
+    let quirks = Platform::Chip8.quirks();
+    for opcode in [0x0000, 0x0123, 0x5121, 0x8128, 0x912F, 0xE19F, 0xF1FF, 0xF102, 0xF0F0] {
+        assert_eq!(Instruction::decode(opcode), Instruction::Data(opcode));
+        assert_eq!(disassemble(opcode, &quirks), format!("DW {:04x}", opcode));
+    }
+}
+
+#[test]
+fn from_memory() {
+    let mut emulation = Emulation::new(Platform::Chip8, 0);
+    emulation.load_rom(&[0x00, 0xE0, 0xA2, 0x2A, 0xF0, 0x90]).unwrap();
+    assert_eq!(emulation.disassemble(0x200), "CLS");
+    assert_eq!(emulation.disassemble(0x202), "LD I, 22a");
+    assert_eq!(emulation.disassemble(0x204), "DW f090");
+}
+
+#[test]
+fn jump_with_offset_follows_the_quirk() {
+    assert_eq!(disassemble(0xB345, &Platform::Chip8.quirks()), "JP V0, 345");
+    assert_eq!(disassemble(0xB345, &Platform::SuperChip.quirks()), "JP V3, 345");
+
+    let mut emulation = Emulation::new(Platform::SuperChip, 0);
+    emulation.load_rom(&[0xB3, 0x45]).unwrap();
+    assert_eq!(emulation.disassemble(0x200), "JP V3, 345");
+}
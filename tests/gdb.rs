@@ -0,0 +1,101 @@
+// Drives the `--gdb` stub of a headless run with a scripted RSP client
+
+mod common;
+
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    process::{Command, Stdio},
+};
+
+use common::{connect, free_port, EMULATOR};
+
+struct Client {
+    stream: TcpStream,
+}
+
+impl Client {
+
+    fn connect(port: u16) -> Self {
+        Self { stream: connect(port) }
+    }
+
+    fn request(&mut self, command: &str) -> String {
+        let checksum = command.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+        write!(self.stream, "${}#{:02x}", command, checksum).unwrap();
+        self.reply()
+    }
+
+    // Skips acks and checks the checksum of the packet that follows
+    fn reply(&mut self) -> String {
+        let mut byte = [0];
+        loop {
+            self.stream.read_exact(&mut byte).unwrap();
+            if byte[0] == b'$' {
+                break;
+            }
+            assert_eq!(byte[0], b'+');
+        }
+
+        let mut data = Vec::new();
+        loop {
+            self.stream.read_exact(&mut byte).unwrap();
+            if byte[0] == b'#' {
+                break;
+            }
+            data.push(byte[0]);
+        }
+        let mut checksum = [0; 2];
+        self.stream.read_exact(&mut checksum).unwrap();
+        let expected = data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+        assert_eq!(u8::from_str_radix(std::str::from_utf8(&checksum).unwrap(), 16).unwrap(), expected);
+
+        self.stream.write_all(b"+").unwrap();
+        String::from_utf8(data).unwrap()
+    }
+
+}
+
+#[test]
+fn breakpoints_registers_and_memory() {
+    let port = free_port();
+    let emulator = Command::new(EMULATOR)
+        .args(["roms/IBM_Logo.ch8", "--headless", "30", "--gdb", &port.to_string()])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut gdb = Client::connect(port);
+
+    assert!(gdb.request("qSupported:multiprocess+").contains("qXfer:features:read+"));
+    assert!(gdb.request("qXfer:features:read:target.xml:0,fff").starts_with("l<?xml"));
+    assert_eq!(gdb.request("?"), "S05");
+
+    // V0-VF, I, PC, SP, DT, ST with PC at 0x200
+    assert_eq!(gdb.request("g"), format!("{}0000{}{}", "00".repeat(16), "0002", "00".repeat(3)));
+    assert_eq!(gdb.request("m200,4"), "00e0a22a");
+
+    // 00E0, A22A, 600C, 6108, then stop before the first DXYN
+    assert_eq!(gdb.request("Z0,208,2"), "OK");
+    assert_eq!(gdb.request("c"), "S05");
+    assert_eq!(gdb.request("p11"), "0802");
+    assert_eq!(gdb.request("p0"), "0c");
+    assert_eq!(gdb.request("p1"), "08");
+    assert_eq!(gdb.request("p10"), "2a02");
+
+    assert_eq!(gdb.request("P0=0f"), "OK");
+    assert_eq!(gdb.request("p0"), "0f");
+    assert_eq!(gdb.request("s"), "S05");
+    assert_eq!(gdb.request("p11"), "0a02");
+
+    assert_eq!(gdb.request("M300,2:abcd"), "OK");
+    assert_eq!(gdb.request("m300,2"), "abcd");
+    assert_eq!(gdb.request("mfff,2"), "E01");
+
+    assert_eq!(gdb.request("z0,208,2"), "OK");
+    assert_eq!(gdb.request("D"), "OK");
+
+    let output = emulator.wait_with_output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains('#'));
+}
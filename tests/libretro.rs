@@ -0,0 +1,103 @@
+// A stub libretro frontend: loads the core with dlopen and runs a ROM headless
+
+use std::{
+    ffi::{c_char, c_void, CString},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use libloading::{Library, Symbol};
+
+#[repr(C)]
+struct GameInfo {
+    path: *const c_char,
+    data: *const c_void,
+    size: usize,
+    meta: *const c_char,
+}
+
+static FRAME: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+static AUDIO_FRAMES: Mutex<usize> = Mutex::new(0);
+
+unsafe extern "C" fn environment(cmd: u32, _data: *mut c_void) -> bool {
+    // Only the pixel format is asked for
+    cmd == 10
+}
+
+unsafe extern "C" fn video_refresh(data: *const c_void, width: u32, height: u32, pitch: usize) {
+    assert_eq!(pitch, width as usize * 4);
+    let pixels = std::slice::from_raw_parts(data as *const u32, (width * height) as usize);
+    *FRAME.lock().unwrap() = pixels.to_vec();
+}
+
+unsafe extern "C" fn audio_sample_batch(_data: *const i16, frames: usize) -> usize {
+    *AUDIO_FRAMES.lock().unwrap() += frames;
+    frames
+}
+
+unsafe extern "C" fn input_poll() {}
+
+unsafe extern "C" fn input_state(_port: u32, _device: u32, _index: u32, _id: u32) -> i16 {
+    0
+}
+
+fn core_path() -> PathBuf {
+    // Test binaries live in target/<profile>/deps next to the cdylib, `cargo build` also copies it one level up
+    let exe = std::env::current_exe().unwrap();
+    let deps = exe.parent().unwrap();
+    let name = format!("{}chip_8_emulator{}", std::env::consts::DLL_PREFIX, std::env::consts::DLL_SUFFIX);
+    [deps.join(&name), deps.parent().unwrap().join(&name)]
+        .into_iter()
+        .find(|path| path.exists())
+        .unwrap_or_else(|| deps.join(name))
+}
+
+#[test]
+fn runs_ibm_logo_through_libretro() {
+    unsafe {
+        let core = Library::new(core_path()).expect("core should be built alongside the tests");
+
+        let api_version: Symbol<unsafe extern "C" fn() -> u32> = core.get(b"retro_api_version").unwrap();
+        assert_eq!(api_version(), 1);
+
+        core.get::<unsafe extern "C" fn(unsafe extern "C" fn(u32, *mut c_void) -> bool)>(b"retro_set_environment").unwrap()(environment);
+        core.get::<unsafe extern "C" fn(unsafe extern "C" fn(*const c_void, u32, u32, usize))>(b"retro_set_video_refresh").unwrap()(video_refresh);
+        core.get::<unsafe extern "C" fn(unsafe extern "C" fn(*const i16, usize) -> usize)>(b"retro_set_audio_sample_batch").unwrap()(audio_sample_batch);
+        core.get::<unsafe extern "C" fn(unsafe extern "C" fn())>(b"retro_set_input_poll").unwrap()(input_poll);
+        core.get::<unsafe extern "C" fn(unsafe extern "C" fn(u32, u32, u32, u32) -> i16)>(b"retro_set_input_state").unwrap()(input_state);
+        core.get::<unsafe extern "C" fn()>(b"retro_init").unwrap()();
+
+        let rom = CString::new(concat!(env!("CARGO_MANIFEST_DIR"), "/roms/IBM_Logo.ch8")).unwrap();
+        let game = GameInfo { path: rom.as_ptr(), data: std::ptr::null(), size: 0, meta: std::ptr::null() };
+        let load_game: Symbol<unsafe extern "C" fn(*const GameInfo) -> bool> = core.get(b"retro_load_game").unwrap();
+        assert!(load_game(&game));
+
+        let run: Symbol<unsafe extern "C" fn()> = core.get(b"retro_run").unwrap();
+        for _ in 0..60 {
+            run();
+        }
+
+        let frame = FRAME.lock().unwrap().clone();
+        assert_eq!(frame.len(), 64 * 32);
+        assert!(frame.iter().any(|&pixel| pixel != 0), "the logo should have been drawn");
+        assert_eq!(*AUDIO_FRAMES.lock().unwrap(), 60 * 735);
+
+        // Save, run on, restore, and the next frame should match the one after saving
+        let serialize_size: Symbol<unsafe extern "C" fn() -> usize> = core.get(b"retro_serialize_size").unwrap();
+        let serialize: Symbol<unsafe extern "C" fn(*mut c_void, usize) -> bool> = core.get(b"retro_serialize").unwrap();
+        let unserialize: Symbol<unsafe extern "C" fn(*const c_void, usize) -> bool> = core.get(b"retro_unserialize").unwrap();
+        let mut state = vec![0u8; serialize_size()];
+        assert!(serialize(state.as_mut_ptr() as *mut c_void, state.len()));
+        run();
+        let expected = FRAME.lock().unwrap().clone();
+        for _ in 0..10 {
+            run();
+        }
+        assert!(unserialize(state.as_ptr() as *const c_void, state.len()));
+        run();
+        assert_eq!(*FRAME.lock().unwrap(), expected);
+
+        core.get::<unsafe extern "C" fn()>(b"retro_unload_game").unwrap()();
+        core.get::<unsafe extern "C" fn()>(b"retro_deinit").unwrap()();
+    }
+}
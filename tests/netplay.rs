@@ -0,0 +1,45 @@
+// Runs a host and a joining player as two headless processes on localhost
+
+mod common;
+
+use std::process::{Child, Command, Output, Stdio};
+
+use common::{free_port, EMULATOR};
+
+fn player(args: &[&str]) -> Child {
+    Command::new(EMULATOR)
+        .args(["roms/tetris.rom", "--headless", "300"])
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap()
+}
+
+fn run_pair(host_args: &[&str], join_args: &[&str]) -> (Output, Output) {
+    let port = free_port().to_string();
+    let address = format!("127.0.0.1:{}", port);
+    let host = player(&[&["--host", &port], host_args].concat());
+    let join = player(&[&["--join", &address], join_args].concat());
+    (host.wait_with_output().unwrap(), join.wait_with_output().unwrap())
+}
+
+#[test]
+fn players_stay_in_lockstep() {
+    // Only the host's seed counts, the joining player takes it over
+    let (host, join) = run_pair(&["--seed", "7", "--input-delay", "3"], &["--seed", "8"]);
+
+    assert!(host.status.success(), "{}", String::from_utf8_lossy(&host.stderr));
+    assert!(join.status.success(), "{}", String::from_utf8_lossy(&join.stderr));
+    assert!(!host.stdout.is_empty());
+    assert_eq!(host.stdout, join.stdout);
+}
+
+#[test]
+fn desync_is_detected() {
+    let (host, join) = run_pair(&["--ipf", "10"], &["--ipf", "11"]);
+
+    assert!(!host.status.success());
+    assert!(!join.status.success());
+    assert!(String::from_utf8_lossy(&host.stderr).contains("desync"));
+}
@@ -0,0 +1,157 @@
+// Programs that used to panic the interpreter, and a seeded run of random ROMs the way the
+// `rom` fuzz target feeds them
+
+use chip_8_emulator::emulation::{quirks::Platform, Emulation};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const PLATFORMS: [Platform; 4] = [Platform::Chip8, Platform::ModernChip8, Platform::SuperChip, Platform::XoChip];
+
+fn run(platform: Platform, rom: &[u8], frames: u32) -> Emulation {
+    let mut emulation = Emulation::new(platform, 0);
+    emulation.load_rom(rom).unwrap();
+    for _ in 0..frames {
+        emulation.run_frame(1000);
+    }
+    emulation
+}
+
+#[test]
+fn index_past_end_of_memory() {
+    // I = 0xFFF, draw, BCD, store, load, then I += 0xFF
+    let rom = [0xAF, 0xFF, 0xD0, 0x1F, 0xF0, 0x33, 0xFF, 0x55, 0xFF, 0x65, 0x60, 0xFF, 0xF0, 0x1E, 0x12, 0x0C];
+    for platform in PLATFORMS {
+        run(platform, &rom, 2);
+    }
+}
+
+#[test]
+fn return_with_empty_stack() {
+    // 00EE, then V0 = 1 to show execution went on
+    let emulation = run(Platform::Chip8, &[0x00, 0xEE, 0x60, 0x01, 0x12, 0x04], 1);
+    assert_eq!(emulation.chip8_data.var_registers[0], 1);
+}
+
+#[test]
+fn call_with_full_stack() {
+    // Calls that never return
+    let mut emulation = run(Platform::Chip8, &[0x22, 0x00, 0x22, 0x00], 1);
+    assert_eq!(emulation.chip8_data.stack.len(), 16);
+
+    let state = emulation.save_state();
+    emulation.load_state(&state).unwrap();
+    assert_eq!(emulation.chip8_data.stack.len(), 16);
+}
+
+#[test]
+fn call_runs_the_whole_subroutine() {
+    // CALL 206, then loop; the subroutine sets V0 = 7 and returns
+    let emulation = run(Platform::Chip8, &[0x22, 0x06, 0x12, 0x02, 0x00, 0x00, 0x60, 0x07, 0x00, 0xEE], 1);
+    assert_eq!(emulation.chip8_data.var_registers[0], 7);
+    assert_eq!(emulation.chip8_data.pc, 0x202);
+    assert!(emulation.chip8_data.stack.is_empty());
+}
+
+#[test]
+fn add_to_index() {
+    // I = 0x300, V3 = 0x25, I += V3
+    let emulation = run(Platform::Chip8, &[0xA3, 0x00, 0x63, 0x25, 0xF3, 0x1E, 0x12, 0x06], 1);
+    assert_eq!(emulation.chip8_data.index, 0x325);
+}
+
+#[test]
+fn skip_if_registers_differ() {
+    // V0 = 1, skip V1 = 5 if V0 != V1, V2 = 1, then loop
+    let emulation = run(Platform::Chip8, &[0x60, 0x01, 0x90, 0x10, 0x61, 0x05, 0x62, 0x01, 0x12, 0x08], 1);
+    assert_eq!(emulation.chip8_data.var_registers[1..3], [0, 1]);
+}
+
+#[test]
+fn font_digits() {
+    // I = font sprite of V0 for 0 and for 0x1A, which only counts the low nibble
+    let emulation = run(Platform::Chip8, &[0xF0, 0x29, 0x61, 0x1A, 0xF1, 0x29, 0x12, 0x08], 1);
+    assert_eq!(emulation.chip8_data.index, 0xA * 5);
+    let emulation = run(Platform::Chip8, &[0xF0, 0x29, 0x12, 0x02], 1);
+    assert_eq!(emulation.chip8_data.index, 0);
+}
+
+// Besides not panicking, a run has to be repeatable from its seed and from a save state
+#[test]
+fn random_roms() {
+    let mut rng = StdRng::seed_from_u64(0xC8);
+    for round in 0..200 {
+        let mut rom = vec![0; rng.gen_range(2..512)];
+        rng.fill(rom.as_mut_slice());
+        let keys: Vec<[bool; 16]> = (0..10).map(|_| rng.gen()).collect();
+        let platform = PLATFORMS[round % 4];
+
+        let mut emulation = Emulation::new(platform, round as u64);
+        let mut replay = Emulation::new(platform, round as u64);
+        emulation.load_rom(&rom).unwrap();
+        replay.load_rom(&rom).unwrap();
+        for (frame, keys) in keys.iter().enumerate() {
+            emulation.keypad = *keys;
+            emulation.run_frame(1000);
+            if frame == 4 {
+                replay.load_state(&emulation.save_state()).unwrap();
+            } else if frame > 4 {
+                replay.keypad = *keys;
+                replay.run_frame(1000);
+            }
+        }
+
+        assert!(emulation.chip8_data.pc < 0x1000);
+        assert!(emulation.chip8_data.stack.len() <= 16);
+        assert_eq!(replay.save_state(), emulation.save_state(), "ROM {} diverged after loading a state", round);
+    }
+}
+
+#[test]
+fn hires_switch() {
+    // HIGH, then a one pixel sprite at (100, 50) that only fits on the 128x64 screen
+    let rom = [0x00, 0xFF, 0x60, 0x64, 0x61, 0x32, 0xA2, 0x0C, 0xD0, 0x11, 0x12, 0x0A, 0x80];
+    let emulation = run(Platform::SuperChip, &rom, 1);
+    assert_eq!((emulation.display.width, emulation.display.height), (128, 64));
+    assert_eq!(emulation.display.get(100, 50), 1);
+
+    let state = emulation.save_state();
+    let mut loaded = Emulation::new(Platform::SuperChip, 0);
+    loaded.load_state(&state).unwrap();
+    assert_eq!(loaded.save_state(), state);
+
+    // LOW again, the screen is blank at the old size
+    let emulation = run(Platform::SuperChip, &[0x00, 0xFF, 0x00, 0xFE, 0x12, 0x04], 1);
+    assert_eq!((emulation.display.width, emulation.display.height), (64, 32));
+    assert!(emulation.display.pixels().iter().all(|pixel| *pixel == 0));
+}
+
+#[test]
+fn large_sprites() {
+    // HIGH, I = 16 rows of two 0xFF bytes, DXY0 at (0, 0)
+    let mut rom = vec![0x00, 0xFF, 0xA2, 0x08, 0xD0, 0x00, 0x12, 0x06];
+    rom.extend([0xFF; 32]);
+    let emulation = run(Platform::SuperChip, &rom, 1);
+    assert_eq!(lit_pixels(&emulation), 256);
+    assert_eq!(emulation.display.get(15, 15), 1);
+    assert_eq!(emulation.display.get(16, 0), 0);
+}
+
+#[test]
+fn large_sprites_in_low_resolution() {
+    // The same sprite without switching to high resolution first
+    let mut rom = vec![0xA2, 0x06, 0xD0, 0x00, 0x12, 0x04];
+    rom.extend([0xFF; 32]);
+
+    // SUPER-CHIP 1.1 draws it 8 pixels wide, XO-CHIP draws all of it
+    assert_eq!(lit_pixels(&run(Platform::SuperChip, &rom, 1)), 8 * 16);
+    assert_eq!(lit_pixels(&run(Platform::XoChip, &rom, 1)), 16 * 16);
+    // and CHIP-8 draws nothing at all
+    for platform in [Platform::Chip8, Platform::ModernChip8] {
+        let emulation = run(platform, &rom, 1);
+        assert_eq!(lit_pixels(&emulation), 0);
+        assert_eq!(emulation.chip8_data.var_registers[0xF], 0);
+    }
+}
+
+fn lit_pixels(emulation: &Emulation) -> usize {
+    emulation.display.pixels().iter().filter(|pixel| **pixel != 0).count()
+}
@@ -0,0 +1,70 @@
+// A script backed by plain Rust, checks each hook fires and what it can change
+
+use std::sync::{Arc, Mutex};
+
+use chip_8_emulator::emulation::{
+    quirks::Platform,
+    script::{OverlayText, Script, ScriptContext},
+    Emulation,
+};
+
+// VA = 123, I = 0x300, BCD of VA at I, then loop forever
+const ROM: [u8; 8] = [0x6A, 123, 0xA3, 0x00, 0xFA, 0x33, 0x12, 0x06];
+
+#[derive(Default)]
+struct Log {
+    pcs: Vec<u16>,
+    writes: Vec<(u16, u8)>,
+    frames: u32,
+}
+
+struct Recorder {
+    log: Arc<Mutex<Log>>,
+}
+
+impl Script for Recorder {
+
+    fn on_attach(&mut self, context: &mut ScriptContext) {
+        context.hook_pc(0x204);
+        context.keys[5] = true;
+    }
+
+    fn on_frame_end(&mut self, context: &mut ScriptContext) {
+        let mut log = self.log.lock().unwrap();
+        log.frames += 1;
+        context.draw_text(1, 2, &format!("FRAME {}", log.frames));
+    }
+
+    // Changes the number before it is converted
+    fn on_pc(&mut self, pc: u16, context: &mut ScriptContext) {
+        self.log.lock().unwrap().pcs.push(pc);
+        context.chip.var_registers[0xA] = 45;
+    }
+
+    fn on_memory_write(&mut self, address: u16, value: u8, _context: &mut ScriptContext) {
+        self.log.lock().unwrap().writes.push((address, value));
+    }
+
+}
+
+#[test]
+fn hooks() {
+    let log = Arc::new(Mutex::new(Log::default()));
+    let mut emulation = Emulation::new(Platform::Chip8, 0);
+    emulation.load_rom(&ROM).unwrap();
+    emulation.attach_script(Box::new(Recorder { log: log.clone() }));
+
+    emulation.run_frame(10);
+    emulation.run_frame(10);
+
+    let log = log.lock().unwrap();
+    assert_eq!(log.pcs, [0x204]);
+    assert_eq!(log.writes, [(0x302, 5), (0x301, 4), (0x300, 0)]);
+    assert_eq!(log.frames, 2);
+    assert_eq!(emulation.chip8_data.memory[0x300..0x303], [0, 4, 5]);
+    assert!(emulation.keypad[5]);
+    assert_eq!(emulation.overlay(), [OverlayText { x: 1, y: 2, text: "FRAME 2".to_string() }]);
+
+    assert!(emulation.detach_script().is_some());
+    assert!(emulation.overlay().is_empty());
+}